@@ -0,0 +1,53 @@
+//! Compares canonical string formatting against the previous clone-and-convert path
+//!
+//! Run with `cargo bench --bench display`.
+use bson::oid::ObjectId;
+use mongodb_id::ID;
+use std::{fmt, hint::black_box, time::Instant};
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn time<F: FnMut()>(label: &str, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_op = start.elapsed() / ITERATIONS;
+    println!("{:<40} {:>8?}/op", label, per_op);
+}
+
+/// The formatting path `Display` used before `write_canonical`
+struct PreviousDisplay<'a>(&'a ID);
+impl fmt::Display for PreviousDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from(self.0.clone()))
+    }
+}
+
+fn main() {
+    let ids = vec![
+        (
+            "ObjectId",
+            ID::ObjectId(ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap()),
+        ),
+        ("String", ID::String("user-1234567890".to_string())),
+        ("Int64", ID::Int64(1_234_567_890)),
+    ];
+    for (variant, id) in &ids {
+        time(&format!("{} previous to_string", variant), || {
+            black_box(PreviousDisplay(black_box(id)).to_string());
+        });
+        time(&format!("{} to_string", variant), || {
+            black_box(black_box(id).to_string());
+        });
+        let mut buf = String::with_capacity(32);
+        time(
+            &format!("{} write_canonical (reused buffer)", variant),
+            || {
+                buf.clear();
+                black_box(id).write_canonical(&mut buf).unwrap();
+                black_box(&buf);
+            },
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Benchmarks for the hot paths of `ID`: parsing, serialization, Display,
+//! hashing and bulk deserialization
+//!
+//! Run with `cargo bench --bench suite`. Results are written as JSON to
+//! `target/bench-baseline.json` (or `$BENCH_BASELINE`) and each run reports the
+//! change against the previous file, so a visitor or conversion change can be
+//! measured by running the suite before and after it.
+use bson::{oid::ObjectId, Bson};
+use mongodb_id::{HashedId, HashedIdMap, IdRef, ID};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    env, fs,
+    hash::{Hash, Hasher},
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const TARGET: Duration = Duration::from_millis(300);
+
+struct Suite {
+    baseline: BTreeMap<String, f64>,
+    results: BTreeMap<String, f64>,
+}
+
+impl Suite {
+    fn bench<T, F: FnMut() -> T>(&mut self, name: &str, mut f: F) {
+        // warm up and estimate how many iterations fit in the target time
+        let start = Instant::now();
+        let mut iterations = 0_u64;
+        while start.elapsed() < TARGET / 10 {
+            black_box(f());
+            iterations += 1;
+        }
+        let iterations = iterations * 10;
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box(f());
+        }
+        let ns = start.elapsed().as_nanos() as f64 / iterations as f64;
+        match self.baseline.get(name) {
+            Some(previous) => println!(
+                "{:<36} {:>10.1} ns/op  {:>+7.1}%",
+                name,
+                ns,
+                (ns - previous) / previous * 100.0
+            ),
+            None => println!("{:<36} {:>10.1} ns/op", name, ns),
+        }
+        self.results.insert(name.to_string(), ns);
+    }
+}
+
+fn sample_ids() -> Vec<(&'static str, ID)> {
+    vec![
+        (
+            "oid",
+            ID::ObjectId(ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap()),
+        ),
+        ("string", ID::String("user-1234567890".to_string())),
+        ("int64", ID::Int64(1_234_567_890)),
+    ]
+}
+
+fn main() {
+    let path = env::var("BENCH_BASELINE").unwrap_or_else(|_| "target/bench-baseline.json".into());
+    let baseline = fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let mut suite = Suite {
+        baseline,
+        results: BTreeMap::new(),
+    };
+
+    suite.bench("parse/oid", || {
+        ID::from_string("$oid:5eaefffa00c9fdf000c46fdc")
+    });
+    suite.bench("parse/string", || ID::from_string("user-1234567890"));
+
+    for (variant, id) in sample_ids() {
+        let json = serde_json::to_string(&id).unwrap();
+        let bson = id.to_bson();
+        suite.bench(&format!("json_serialize/{}", variant), || {
+            serde_json::to_string(&id).unwrap()
+        });
+        suite.bench(&format!("json_deserialize/{}", variant), || {
+            serde_json::from_str::<ID>(&json).unwrap()
+        });
+        suite.bench(&format!("bson_serialize/{}", variant), || {
+            bson::to_bson(&id).unwrap()
+        });
+        suite.bench(&format!("bson_deserialize/{}", variant), || {
+            bson::from_bson::<ID>(bson.clone()).unwrap()
+        });
+        suite.bench(&format!("display/{}", variant), || id.to_string());
+        suite.bench(&format!("hash/{}", variant), || {
+            let mut hasher = DefaultHasher::new();
+            id.hash(&mut hasher);
+            hasher.finish()
+        });
+    }
+
+    let ids: Vec<ID> = (0..1000)
+        .map(|i| match i % 3 {
+            0 => ID::ObjectId(ObjectId::new()),
+            1 => ID::String(format!("user-{}", i)),
+            _ => ID::Int64(i),
+        })
+        .collect();
+    let json = serde_json::to_string(&ids).unwrap();
+    let bson = Bson::Array(ids.iter().map(ID::to_bson).collect());
+    suite.bench("bulk_to_bson_map/1000", || {
+        Bson::Array(ids.iter().map(ID::to_bson).collect())
+    });
+    suite.bench("bulk_to_bson/1000", || ID::many_to_bson(&ids));
+    suite.bench("bulk_from_bson_array/1000", || {
+        mongodb_id::ids_from_bson_array(&bson).unwrap()
+    });
+    suite.bench("bulk_json_deserialize/1000", || {
+        serde_json::from_str::<Vec<ID>>(&json).unwrap()
+    });
+    suite.bench("bulk_json_deserialize_ref/1000", || {
+        serde_json::from_str::<Vec<IdRef>>(&json).unwrap()
+    });
+    suite.bench("bulk_bson_deserialize/1000", || {
+        bson::from_bson::<Vec<ID>>(bson.clone()).unwrap()
+    });
+
+    let map: HashMap<ID, usize> = ids.iter().cloned().zip(0..).collect();
+    let hashed_map: HashedIdMap<usize> = ids.iter().cloned().map(HashedId::new).zip(0..).collect();
+    let probe = ids[500].clone();
+    let hashed_probe = HashedId::new(probe.clone());
+    suite.bench("map_probe/id", || map.get(&probe).copied());
+    suite.bench("map_probe/hashed_id", || {
+        hashed_map.get(&hashed_probe).copied()
+    });
+
+    let results = serde_json::to_string_pretty(&suite.results).unwrap();
+    if let Err(e) = fs::write(&path, results) {
+        eprintln!("unable to write baseline {}: {}", path, e);
+    }
+}
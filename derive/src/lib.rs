@@ -0,0 +1,141 @@
+//! The `#[mongo_model]` attribute, re-exported by `mongodb_id` with the
+//! `derive` feature
+use proc_macro::TokenStream;
+use proc_macro2::TokenTree;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, Ident, LitStr, Meta,
+};
+
+/// Store a struct's ID field as `_id` and accept either `_id` or `id` on read
+///
+/// The field is the one marked `#[mongo_id]`, or else the one named `id`. Put
+/// the attribute above `#[derive(Serialize, Deserialize)]` so serde sees the
+/// attributes it adds.
+///
+/// Serializers can't tell BSON from JSON, so plain `serde_json` output also
+/// has `_id`. Either convert it with `mongodb_id::model::to_json_with_id`, or
+/// pass `json = "<name>"`: `#[mongo_model(json = "id")] struct User` also
+/// defines `UserJson`, a copy of `User` with the same derives whose ID field
+/// is written as `id` and read from `id` or `_id`, with `From` conversions both
+/// ways.
+#[proc_macro_attribute]
+pub fn mongo_model(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut json_name: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("json") {
+            json_name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("mongo_model only takes `json = \"<name>\"`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+    let mut input = parse_macro_input!(input as DeriveInput);
+    let expanded = add_id_attributes(&mut input).and_then(|index| match &json_name {
+        Some(name) => json_view(&input, index, name),
+        None => Ok(quote!(#input)),
+    });
+    match expanded {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn named_fields(
+    input: &mut DeriveInput,
+) -> Result<&mut syn::punctuated::Punctuated<syn::Field, syn::token::Comma>, Error> {
+    let span = input.ident.span();
+    match &mut input.data {
+        Data::Struct(data) => match &mut data.fields {
+            Fields::Named(fields) => Ok(&mut fields.named),
+            _ => Err(Error::new(span, "mongo_model needs named fields")),
+        },
+        _ => Err(Error::new(span, "mongo_model only supports structs")),
+    }
+}
+
+/// Rename the ID field to `_id`, returning its index
+fn add_id_attributes(input: &mut DeriveInput) -> Result<usize, Error> {
+    let span = input.ident.span();
+    let fields = named_fields(input)?;
+
+    let marked = fields
+        .iter()
+        .position(|f| f.attrs.iter().any(|a| a.path().is_ident("mongo_id")));
+    let index = marked
+        .or_else(|| {
+            fields
+                .iter()
+                .position(|f| f.ident.as_ref().is_some_and(|i| i == "id"))
+        })
+        .ok_or_else(|| Error::new(span, "no field named `id` or marked #[mongo_id]"))?;
+
+    let field = &mut fields[index];
+    field.attrs.retain(|a| !a.path().is_ident("mongo_id"));
+    for attr in &field.attrs {
+        let renamed = match &attr.meta {
+            Meta::List(list) if list.path.is_ident("serde") => list
+                .tokens
+                .clone()
+                .into_iter()
+                .any(|t| matches!(&t, TokenTree::Ident(i) if i == "rename" || i == "alias")),
+            _ => false,
+        };
+        if renamed {
+            return Err(Error::new(
+                attr.span(),
+                "mongo_model sets the serde rename and alias of the ID field",
+            ));
+        }
+    }
+    field
+        .attrs
+        .push(syn::parse_quote!(#[serde(rename = "_id", alias = "id")]));
+    Ok(index)
+}
+
+/// `input` followed by its JSON view, whose ID field is named `name`
+fn json_view(
+    input: &DeriveInput,
+    index: usize,
+    name: &LitStr,
+) -> Result<proc_macro2::TokenStream, Error> {
+    let model = &input.ident;
+    let view_ident = format_ident!("{}Json", model);
+    let mut view = input.clone();
+    view.ident = view_ident.clone();
+    view.attrs.retain(|a| !a.path().is_ident("doc"));
+    let doc = format!(
+        " [`{}`] for JSON, its ID field written as `{}`",
+        model,
+        name.value()
+    );
+    view.attrs.insert(0, syn::parse_quote!(#[doc = #doc]));
+    let fields = named_fields(&mut view)?;
+    let id_field = &mut fields[index];
+    id_field.attrs.pop();
+    id_field
+        .attrs
+        .push(syn::parse_quote!(#[serde(rename = #name, alias = "_id")]));
+    let names: Vec<Ident> = fields.iter().filter_map(|f| f.ident.clone()).collect();
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        #input
+
+        #view
+
+        impl #impl_generics ::std::convert::From<#model #ty_generics> for #view_ident #ty_generics #where_clause {
+            fn from(model: #model #ty_generics) -> Self {
+                #view_ident { #(#names: model.#names),* }
+            }
+        }
+
+        impl #impl_generics ::std::convert::From<#view_ident #ty_generics> for #model #ty_generics #where_clause {
+            fn from(view: #view_ident #ty_generics) -> Self {
+                #model { #(#names: view.#names),* }
+            }
+        }
+    })
+}
@@ -0,0 +1,3 @@
+//! Builds the C interface of `mongodb_id` as `libmongodb_id_ffi.a` and a
+//! shared library, for programs including `include/mongodb_id.h`
+pub use mongodb_id::ffi::*;
@@ -0,0 +1,71 @@
+//! Compiles `tests/smoke.c` against `include/mongodb_id.h` and the static
+//! library, so the header can't drift from `src/ffi.rs` unnoticed
+use mongodb_id_ffi::{CId, CIdStatus, CIdStr, CIdTag, CIdValue};
+use std::{env, mem::size_of, path::Path, process::Command};
+
+#[test]
+fn test_c_header_matches() {
+    let ffi_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let test_exe = env::current_exe().unwrap();
+    let target_dir = test_exe.parent().unwrap().parent().unwrap();
+    let library = target_dir.join("libmongodb_id_ffi.a");
+    assert!(library.exists(), "{} was not built", library.display());
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let smoke = Path::new(env!("CARGO_TARGET_TMPDIR")).join("mongodb_id_smoke");
+    let status = Command::new(&cc)
+        .args(["-std=c11", "-Wall", "-Werror", "-I"])
+        .arg(ffi_dir.join("../include"))
+        .arg(ffi_dir.join("tests/smoke.c"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&smoke)
+        .status()
+        .unwrap_or_else(|e| panic!("running {}: {}", cc, e));
+    assert!(
+        status.success(),
+        "smoke.c failed to compile against the header"
+    );
+
+    let output = Command::new(&smoke).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let id = CId {
+        tag: 0,
+        value: CIdValue { int64: 0 },
+    };
+    let offset = &id.value as *const CIdValue as usize - &id as *const CId as usize;
+    let tags = [CIdTag::ObjectId, CIdTag::String, CIdTag::Int64];
+    let statuses = [
+        CIdStatus::Ok,
+        CIdStatus::NullPointer,
+        CIdStatus::InvalidUtf8,
+        CIdStatus::Empty,
+        CIdStatus::Whitespace,
+        CIdStatus::InvalidObjectId,
+        CIdStatus::InvalidTag,
+    ];
+    let values = |values: Vec<u32>| {
+        values
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{} {} {} {} {}\n{}\n{}\n",
+            size_of::<CId>(),
+            size_of::<u32>(),
+            offset,
+            size_of::<CIdValue>(),
+            size_of::<CIdStr>(),
+            values(tags.iter().map(|t| *t as u32).collect()),
+            values(statuses.iter().map(|s| *s as u32).collect()),
+        )
+    );
+}
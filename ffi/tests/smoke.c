@@ -0,0 +1,58 @@
+/* Exercises every declaration of include/mongodb_id.h against the library,
+ * printing the layout of CId and the enum values for the Rust side to
+ * compare. */
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "mongodb_id.h"
+
+#define CHECK(cond)                                   \
+  do {                                                \
+    if (!(cond)) {                                    \
+      fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #cond); \
+      return 1;                                       \
+    }                                                 \
+  } while (0)
+
+static CIdStatus parse(const char *input, CId *out) {
+  return mongodb_id_parse((const uint8_t *)input, strlen(input), out);
+}
+
+int main(void) {
+  CId id;
+  uint8_t buf[64];
+
+  CHECK(parse("$oid:5eaefffa00c9fdf000c46fdc", &id) == CIdStatus_Ok);
+  CHECK(id.tag == CIdTag_ObjectId);
+  CHECK(id.value.object_id[0] == 0x5e && id.value.object_id[11] == 0xdc);
+  CHECK(mongodb_id_format(&id, buf, sizeof buf) == 29);
+  CHECK(strcmp((const char *)buf, "$oid:5eaefffa00c9fdf000c46fdc") == 0);
+
+  CHECK(parse("users/1", &id) == CIdStatus_Ok);
+  CHECK(id.tag == CIdTag_String);
+  CHECK(id.value.string.len == 7 && memcmp(id.value.string.ptr, "users/1", 7) == 0);
+  CHECK(mongodb_id_format(&id, NULL, 0) == 7);
+  mongodb_id_free(&id);
+  mongodb_id_free(&id);
+
+  id.tag = CIdTag_Int64;
+  id.value.int64 = -42;
+  CHECK(mongodb_id_format(&id, buf, sizeof buf) == 3);
+  CHECK(strcmp((const char *)buf, "-42") == 0);
+  id.tag = 7;
+  CHECK(mongodb_id_format(&id, buf, sizeof buf) == SIZE_MAX);
+
+  CHECK(mongodb_id_parse(NULL, 1, &id) == CIdStatus_NullPointer);
+  CHECK(mongodb_id_parse((const uint8_t *)"\xff", 1, &id) == CIdStatus_InvalidUtf8);
+  CHECK(parse("", &id) == CIdStatus_Empty);
+  CHECK(parse(" a", &id) == CIdStatus_Whitespace);
+  CHECK(parse("$oid:zz", &id) == CIdStatus_InvalidObjectId);
+
+  printf("%zu %zu %zu %zu %zu\n", sizeof(CId), sizeof id.tag, offsetof(CId, value),
+         sizeof(CIdValue), sizeof(CIdStr));
+  printf("%d %d %d\n", CIdTag_ObjectId, CIdTag_String, CIdTag_Int64);
+  printf("%d %d %d %d %d %d %d\n", CIdStatus_Ok, CIdStatus_NullPointer, CIdStatus_InvalidUtf8,
+         CIdStatus_Empty, CIdStatus_Whitespace, CIdStatus_InvalidObjectId, CIdStatus_InvalidTag);
+  return 0;
+}
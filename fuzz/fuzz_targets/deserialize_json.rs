@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mongodb_id::ID;
+
+fuzz_target!(|input: &[u8]| {
+    if let Ok(id) = serde_json::from_slice::<ID>(input) {
+        let json = serde_json::to_vec(&id).unwrap();
+        assert_eq!(serde_json::from_slice::<ID>(&json).unwrap(), id);
+    }
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mongodb_id::ID;
+
+fuzz_target!(|input: &[u8]| {
+    if let Ok(id) = ID::from_bytes(input) {
+        assert_eq!(id.to_bytes(), input);
+    }
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mongodb_id::ID;
+
+fuzz_target!(|input: &str| {
+    // the canonical string of any parsed ID parses back to the same ID
+    let id = ID::from_string(input);
+    assert_eq!(ID::from_string(id.to_string()), id);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mongodb_id::ID;
+
+fuzz_target!(|input: &str| {
+    if let Ok(id) = ID::parse_strict(input) {
+        assert_eq!(ID::parse_strict(&id.to_string()), Ok(id));
+    }
+});
@@ -0,0 +1,67 @@
+/* C interface to the mongodb_id crate, built with the "ffi" feature.
+ * Link against libmongodb_id_ffi from the ffi crate of this workspace.
+ * Kept in sync with src/ffi.rs, checked by ffi/tests/c_header.rs, and can
+ * be regenerated with cbindgen using cbindgen.toml. */
+#ifndef MONGODB_ID_H
+#define MONGODB_ID_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Values of CId.tag */
+typedef enum CIdTag {
+  CIdTag_ObjectId = 0,
+  CIdTag_String = 1,
+  CIdTag_Int64 = 2,
+} CIdTag;
+
+typedef enum CIdStatus {
+  CIdStatus_Ok = 0,
+  CIdStatus_NullPointer = 1,
+  CIdStatus_InvalidUtf8 = 2,
+  CIdStatus_Empty = 3,
+  CIdStatus_Whitespace = 4,
+  CIdStatus_InvalidObjectId = 5,
+  CIdStatus_InvalidTag = 6,
+} CIdStatus;
+
+/* UTF-8 bytes of a string ID, not NUL terminated */
+typedef struct CIdStr {
+  uint8_t *ptr;
+  size_t len;
+} CIdStr;
+
+typedef union CIdValue {
+  uint8_t object_id[12];
+  CIdStr string;
+  int64_t int64;
+} CIdValue;
+
+/* `tag` holds a CIdTag value saying which field of `value` is set; other
+ * values are rejected with CIdStatus_InvalidTag. String payloads filled in by
+ * mongodb_id_parse are owned by Rust and released with mongodb_id_free. */
+typedef struct CId {
+  uint32_t tag;
+  CIdValue value;
+} CId;
+
+/* Parse `len` bytes at `input` strictly, as ID::parse_strict does. */
+CIdStatus mongodb_id_parse(const uint8_t *input, size_t len, CId *out);
+
+/* Write the NUL terminated canonical string of `id` into `buf` if it fits,
+ * like snprintf. Returns its length without the NUL, or SIZE_MAX if `id` is
+ * null, has an invalid tag or holds invalid UTF-8. */
+size_t mongodb_id_format(const CId *id, uint8_t *buf, size_t buf_len);
+
+/* Release the string owned by a CId from mongodb_id_parse. */
+void mongodb_id_free(CId *id);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* MONGODB_ID_H */
@@ -0,0 +1,135 @@
+//! Pseudonymizing IDs in data dumps
+//!
+//! A [`Pseudonymizer`] maps real IDs to synthetic ones with a keyed HMAC, so
+//! the same real ID always gets the same pseudonym and references between
+//! collections stay intact, while the key is needed to link the two.
+use crate::{IDMap, ID};
+use bson::oid::ObjectId;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::{fmt, io};
+
+/// Deterministically maps real IDs to pseudonyms of the same variant
+///
+/// ObjectIds map to ObjectIds, Int64s to non-negative Int64s and Strings to
+/// 32 hex digits. Mappings made with [`Pseudonymizer::pseudonymize`] are
+/// recorded, so they can be saved and real IDs looked up later by whoever
+/// holds the mapping file.
+#[derive(Clone)]
+pub struct Pseudonymizer {
+    mac: Hmac<Sha256>,
+    reverse: IDMap<ID>,
+}
+
+impl Pseudonymizer {
+    /// Pseudonymize with `key`, which should be random and at least 32 bytes
+    pub fn new(key: &[u8]) -> Self {
+        Pseudonymizer {
+            mac: Hmac::new_from_slice(key).expect("HMAC accepts keys of any length"),
+            reverse: IDMap::new(),
+        }
+    }
+
+    /// The pseudonym of `id`, without recording it
+    pub fn pseudonym(&self, id: &ID) -> ID {
+        let mut mac = self.mac.clone();
+        mac.update(&id.to_bytes());
+        let digest = mac.finalize().into_bytes();
+        match id {
+            ID::ObjectId(_) => {
+                let mut bytes = [0; 12];
+                bytes.copy_from_slice(&digest[..12]);
+                ID::ObjectId(ObjectId::with_bytes(bytes))
+            }
+            ID::Int64(_) => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&digest[..8]);
+                ID::Int64(i64::from_be_bytes(bytes) & i64::MAX)
+            }
+            ID::String(_) => {
+                ID::String(digest[..16].iter().map(|b| format!("{:02x}", b)).collect())
+            }
+        }
+    }
+
+    /// The pseudonym of `id`, recording the mapping
+    pub fn pseudonymize(&mut self, id: &ID) -> ID {
+        let pseudonym = self.pseudonym(id);
+        self.reverse.insert(pseudonym.clone(), id.clone());
+        pseudonym
+    }
+
+    /// The real ID a recorded pseudonym stands for
+    pub fn real_id(&self, pseudonym: &ID) -> Option<&ID> {
+        self.reverse.get(pseudonym)
+    }
+
+    /// Number of recorded mappings
+    pub fn len(&self) -> usize {
+        self.reverse.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reverse.is_empty()
+    }
+
+    /// Write the recorded mappings as a JSON array of `[real, pseudonym]` pairs
+    pub fn save_mapping<W: io::Write>(&self, writer: W) -> serde_json::Result<()> {
+        let pairs: Vec<(&ID, &ID)> = self.reverse.iter().map(|(p, r)| (r, p)).collect();
+        serde_json::to_writer(writer, &pairs)
+    }
+
+    /// Record the mappings written by [`Pseudonymizer::save_mapping`]
+    pub fn load_mapping<R: io::Read>(&mut self, reader: R) -> serde_json::Result<()> {
+        let pairs: Vec<(ID, ID)> = serde_json::from_reader(reader)?;
+        self.reverse
+            .extend(pairs.into_iter().map(|(real, pseudonym)| (pseudonym, real)));
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Pseudonymizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pseudonymizer")
+            .field("mappings", &self.reverse.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudonymize() {
+        let mut p = Pseudonymizer::new(b"0123456789abcdef0123456789abcdef");
+        let oid = ID::ObjectId(ObjectId::new());
+        let pseudonym = p.pseudonymize(&oid);
+        assert!(pseudonym.is_object_id());
+        assert_ne!(pseudonym, oid);
+        assert_eq!(p.pseudonymize(&oid), pseudonym);
+        assert_ne!(
+            Pseudonymizer::new(b"another key").pseudonym(&oid),
+            pseudonym
+        );
+
+        let int = p.pseudonymize(&ID::Int64(1));
+        assert!(matches!(int, ID::Int64(i) if i >= 0));
+        assert_ne!(
+            p.pseudonym(&ID::with_string("1")),
+            p.pseudonym(&ID::Int64(1))
+        );
+        let string = p.pseudonymize(&ID::with_string("alice"));
+        assert!(matches!(&string, ID::String(s) if s.len() == 32));
+        assert_eq!(p.real_id(&string), Some(&ID::with_string("alice")));
+        assert!(!format!("{:?}", p).contains("0123"));
+
+        let mut saved = Vec::new();
+        p.save_mapping(&mut saved).unwrap();
+        let mut loaded = Pseudonymizer::new(b"0123456789abcdef0123456789abcdef");
+        loaded.load_mapping(&saved[..]).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.real_id(&int), Some(&ID::Int64(1)));
+        assert_eq!(loaded.real_id(&pseudonym), Some(&oid));
+    }
+}
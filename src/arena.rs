@@ -0,0 +1,151 @@
+//! Bulk parsing into one shared string buffer
+
+use crate::{oid_hex, IdRef, ID};
+use bson::oid::ObjectId;
+use std::{borrow::Cow, ops::Range};
+
+/// IDs parsed in bulk whose string payloads share one growing buffer
+///
+/// Strings are parsed the way [`ID::from_string`](crate::ID::from_string) does
+/// and read back as borrowing [`IdRef`]s. Nothing is freed per ID: [`clear`]
+/// drops every ID at once and keeps the allocations for the next batch.
+///
+/// ```
+/// use mongodb_id::{IdArena, IdRef};
+///
+/// let mut arena = IdArena::new();
+/// arena.extend("user-1\n$oid:5eaefffa00c9fdf000c46fdc".lines());
+/// assert_eq!(arena.get(0), Some(IdRef::from_str_ref("user-1")));
+/// assert!(matches!(arena.get(1), Some(IdRef::ObjectId(_))));
+/// ```
+///
+/// [`clear`]: IdArena::clear
+#[derive(Clone, Debug, Default)]
+pub struct IdArena {
+    text: String,
+    ids: Vec<Slot>,
+}
+
+#[derive(Clone, Debug)]
+enum Slot {
+    ObjectId(ObjectId),
+    String(Range<usize>),
+}
+
+impl IdArena {
+    pub fn new() -> Self {
+        IdArena::default()
+    }
+
+    /// Room for `ids` IDs with `bytes` bytes of strings in total
+    pub fn with_capacity(ids: usize, bytes: usize) -> Self {
+        IdArena {
+            text: String::with_capacity(bytes),
+            ids: Vec::with_capacity(ids),
+        }
+    }
+
+    /// Parse `s` into the arena, returning a view of the new ID
+    pub fn parse(&mut self, s: &str) -> IdRef<'_> {
+        let slot = match s.strip_prefix("$oid:").and_then(oid_hex::parse_oid) {
+            Some(oid) => Slot::ObjectId(oid),
+            None => {
+                let start = self.text.len();
+                self.text.push_str(s);
+                Slot::String(start..self.text.len())
+            }
+        };
+        self.ids.push(slot);
+        self.view(self.ids.len() - 1)
+    }
+
+    pub fn get(&self, index: usize) -> Option<IdRef<'_>> {
+        if index < self.ids.len() {
+            Some(self.view(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = IdRef<'_>> + '_ {
+        (0..self.ids.len()).map(move |index| self.view(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Bytes of string payloads stored
+    pub fn text_len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Drop every ID, keeping the allocated capacity
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.ids.clear();
+    }
+
+    fn view(&self, index: usize) -> IdRef<'_> {
+        match &self.ids[index] {
+            Slot::ObjectId(oid) => IdRef::ObjectId(oid.clone()),
+            Slot::String(range) => IdRef::String(Cow::Borrowed(&self.text[range.clone()])),
+        }
+    }
+}
+
+impl ID {
+    /// Parse `s` into `arena`, see [`IdArena::parse`]
+    pub fn parse_in<'a>(s: &str, arena: &'a mut IdArena) -> IdRef<'a> {
+        arena.parse(s)
+    }
+}
+
+impl<'s> Extend<&'s str> for IdArena {
+    fn extend<I: IntoIterator<Item = &'s str>>(&mut self, iter: I) {
+        for s in iter {
+            self.parse(s);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena() {
+        let inputs = [
+            "a",
+            "$oid:5eaefffa00c9fdf000c46fdc",
+            "$oid:not_valid",
+            "",
+            "bc",
+        ];
+        let mut arena = IdArena::with_capacity(inputs.len(), 16);
+        assert_eq!(
+            ID::parse_in("x", &mut arena),
+            IdRef::String(Cow::Borrowed("x"))
+        );
+        arena.clear();
+        assert!(arena.is_empty());
+
+        arena.extend(inputs.iter().copied());
+        assert_eq!(arena.len(), inputs.len());
+        assert_eq!(arena.text_len(), "a$oid:not_validbc".len());
+        for (id, input) in arena.iter().zip(&inputs) {
+            assert_eq!(id.into_owned(), ID::from_string(*input));
+        }
+        assert_eq!(arena.get(4), Some(IdRef::from_str_ref("bc")));
+        assert_eq!(arena.get(5), None);
+
+        let capacity = arena.text.capacity();
+        arena.clear();
+        assert_eq!(arena.text_len(), 0);
+        assert_eq!(arena.text.capacity(), capacity);
+    }
+}
@@ -0,0 +1,267 @@
+//! Inspect and convert IDs from the command line
+//!
+//! ```text
+//! mongoid inspect [--from <format>] <id>
+//! mongoid convert [--from <format>] --to <format>      < ids.txt
+//! ```
+//!
+//! Formats are `canonical`, `json` (canonical extended JSON), `hex` (ObjectIds
+//! only), `compact-hex` and `base64url`, the last two of the compact bytes.
+//! Input may also be `auto`, the default, which reads JSON if the input starts
+//! with `{` or `"`, 24 hex digits as an ObjectId and anything else as a
+//! canonical string, so integers need `--from json`.
+use base64::URL_SAFE_NO_PAD;
+use mongodb_id::ID;
+use std::{
+    env, fmt,
+    io::{self, BufRead, Write},
+    process,
+};
+
+const USAGE: &str = "usage: mongoid inspect [--from <format>] <id>
+       mongoid convert [--from <format>] --to <format>";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Form {
+    Auto,
+    Canonical,
+    Json,
+    Hex,
+    CompactHex,
+    Base64Url,
+}
+
+impl Form {
+    fn from_arg(arg: &str) -> Result<Self, String> {
+        Ok(match arg {
+            "auto" => Form::Auto,
+            "canonical" => Form::Canonical,
+            "json" => Form::Json,
+            "hex" => Form::Hex,
+            "compact-hex" => Form::CompactHex,
+            "base64url" => Form::Base64Url,
+            _ => return Err(format!("unknown format {:?}", arg)),
+        })
+    }
+}
+
+fn parse(input: &str, from: Form) -> Result<ID, String> {
+    let from = match from {
+        Form::Auto if input.starts_with('{') || input.starts_with('"') => Form::Json,
+        Form::Auto if input.len() == 24 && input.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Form::Hex
+        }
+        Form::Auto => Form::Canonical,
+        from => from,
+    };
+    match from {
+        Form::Canonical => ID::parse_strict(input).map_err(|e| e.to_string()),
+        Form::Json => serde_json::from_str(input).map_err(|e| e.to_string()),
+        Form::Hex => ID::parse_strict(&format!("$oid:{}", input)).map_err(|e| e.to_string()),
+        Form::CompactHex => mongodb_id::io::from_compact_hex(input).map_err(|e| e.to_string()),
+        Form::Base64Url => {
+            let bytes = base64::decode_config(input, URL_SAFE_NO_PAD).map_err(|e| e.to_string())?;
+            ID::from_bytes(&bytes).map_err(|e| e.to_string())
+        }
+        Form::Auto => unreachable!(),
+    }
+}
+
+fn format(id: &ID, to: Form) -> Result<String, String> {
+    Ok(match to {
+        Form::Canonical => id.to_string(),
+        Form::Json => id.to_bson().into_canonical_extjson().to_string(),
+        Form::Hex => match id {
+            ID::ObjectId(o) => o.to_hex(),
+            _ => return Err(format!("{} is not an ObjectId", id)),
+        },
+        Form::CompactHex => id.to_bytes().iter().map(|b| format!("{:02x}", b)).collect(),
+        Form::Base64Url => base64::encode_config(id.to_bytes(), URL_SAFE_NO_PAD),
+        Form::Auto => return Err("auto is only an input format".to_string()),
+    })
+}
+
+struct Inspect<'a>(&'a ID);
+
+impl fmt::Display for Inspect<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = self.0;
+        let variant = match id {
+            ID::ObjectId(_) => "ObjectId",
+            ID::String(_) => "String",
+            ID::Int64(_) => "Int64",
+        };
+        writeln!(f, "variant:       {}", variant)?;
+        writeln!(f, "canonical:     {}", id)?;
+        if let (Some(time), Some(secs)) = (id.chrono_timestamp(), id.timestamp_secs()) {
+            writeln!(f, "timestamp:     {} ({})", time.to_rfc3339(), secs)?;
+        }
+        if let Ok(hex) = format(id, Form::Hex) {
+            writeln!(f, "hex:           {}", hex)?;
+        }
+        writeln!(
+            f,
+            "compact hex:   {}",
+            format(id, Form::CompactHex).unwrap()
+        )?;
+        writeln!(f, "base64url:     {}", format(id, Form::Base64Url).unwrap())?;
+        write!(f, "extended JSON: {}", format(id, Form::Json).unwrap())
+    }
+}
+
+/// Convert every non-blank line of `input`, reporting failures on `errors`
+///
+/// Returns whether every line converted.
+fn convert(
+    input: impl BufRead,
+    mut output: impl Write,
+    mut errors: impl Write,
+    from: Form,
+    to: Form,
+) -> io::Result<bool> {
+    let mut ok = true;
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse(line, from).and_then(|id| format(&id, to)) {
+            Ok(converted) => writeln!(output, "{}", converted)?,
+            Err(e) => {
+                ok = false;
+                writeln!(errors, "line {}: {}", index + 1, e)?;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn run(args: &[String]) -> Result<bool, String> {
+    let (command, mut rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(USAGE.to_string()),
+    };
+    let mut from = Form::Auto;
+    let mut to = None;
+    let mut positional = Vec::new();
+    while let Some((arg, tail)) = rest.split_first() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let (value, tail) = tail
+                    .split_first()
+                    .ok_or_else(|| format!("{} needs a format", arg))?;
+                let form = Form::from_arg(value)?;
+                if arg == "--from" {
+                    from = form;
+                } else {
+                    to = Some(form);
+                }
+                rest = tail;
+                continue;
+            }
+            _ => positional.push(arg.as_str()),
+        }
+        rest = tail;
+    }
+
+    match (command, positional.as_slice(), to) {
+        ("inspect", [input], None) => {
+            println!("{}", Inspect(&parse(input, from)?));
+            Ok(true)
+        }
+        ("convert", [], Some(to)) => {
+            let stdin = io::stdin();
+            convert(stdin.lock(), io::stdout(), io::stderr(), from, to).map_err(|e| e.to_string())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: &str = "5eaefffa00c9fdf000c46fdc";
+
+    #[test]
+    fn test_parse_auto() {
+        let oid = ID::from_string(format!("$oid:{}", OID));
+        assert_eq!(parse(OID, Form::Auto).unwrap(), oid);
+        assert_eq!(parse(&oid.to_string(), Form::Auto).unwrap(), oid);
+        let json = format!(r#"{{"$oid": "{}"}}"#, OID);
+        assert_eq!(parse(&json, Form::Auto).unwrap(), oid);
+        assert_eq!(parse("42", Form::Auto).unwrap(), ID::with_string("42"));
+        assert_eq!(parse("42", Form::Json).unwrap(), ID::Int64(42));
+        assert!(parse(" a", Form::Auto).is_err());
+    }
+
+    #[test]
+    fn test_formats_round_trip() {
+        let ids = [
+            ID::from_string(format!("$oid:{}", OID)),
+            ID::with_string("users/1"),
+            ID::Int64(-7),
+        ];
+        for id in &ids {
+            for to in [Form::Json, Form::CompactHex, Form::Base64Url] {
+                assert_eq!(&parse(&format(id, to).unwrap(), to).unwrap(), id);
+            }
+        }
+        assert_eq!(
+            format(&ids[2], Form::Json).unwrap(),
+            r#"{"$numberLong":"-7"}"#
+        );
+        assert!(format(&ids[1], Form::Hex).is_err());
+        assert_eq!(
+            parse("02fffffffffffffff9", Form::CompactHex).unwrap(),
+            ids[2]
+        );
+        assert!(parse("02+ffffffffffffff9", Form::CompactHex).is_err());
+        assert!(parse("02fffffffffffffff", Form::CompactHex).is_err());
+    }
+
+    #[test]
+    fn test_inspect() {
+        let id = ID::from_string(format!("$oid:{}", OID));
+        let out = Inspect(&id).to_string();
+        assert!(out.contains("variant:       ObjectId"));
+        assert!(out.contains("timestamp:     2020-05-03T17:31:38+00:00 (1588527098)"));
+        assert!(out.contains(&format!("hex:           {}", OID)));
+        assert!(!Inspect(&ID::Int64(1)).to_string().contains("timestamp"));
+    }
+
+    #[test]
+    fn test_convert() {
+        let input = format!("{}\n\n$oid:zz\n{{\"$numberLong\": \"3\"}}\n", OID);
+        let (mut out, mut errors) = (Vec::new(), Vec::new());
+        let ok = convert(
+            input.as_bytes(),
+            &mut out,
+            &mut errors,
+            Form::Auto,
+            Form::Canonical,
+        );
+        assert!(!ok.unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("$oid:{}\n3\n", OID)
+        );
+        assert_eq!(
+            String::from_utf8(errors).unwrap(),
+            "line 3: ID has the $oid: prefix but an invalid ObjectId\n"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! A serializable Bloom filter over IDs
+
+use crate::ID;
+use fnv::FnvHasher;
+use std::{borrow::Borrow, hash::Hasher};
+
+/// Two independent hashes of `id`, stable across processes and releases so
+/// serialized filters stay valid
+fn hash_pair(id: &ID) -> (u64, u64) {
+    // FNV-1a over a variant byte and the payload
+    let mut hasher = FnvHasher::default();
+    match id {
+        ID::ObjectId(o) => {
+            hasher.write(&[0]);
+            hasher.write(&o.bytes());
+        }
+        ID::String(s) => {
+            hasher.write(&[1]);
+            hasher.write(s.as_bytes());
+        }
+        ID::Int64(i) => {
+            hasher.write(&[2]);
+            hasher.write(&i.to_le_bytes());
+        }
+    }
+    let h1 = hasher.finish();
+    // splitmix64 finalizer, forced odd so probes never collapse onto one bit
+    let mut h2 = h1.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    h2 = (h2 ^ (h2 >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h2 = (h2 ^ (h2 >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (h1, (h2 ^ (h2 >> 31)) | 1)
+}
+
+/// A bloom filter over IDs
+///
+/// `maybe_contains` never returns false for an inserted ID, and returns true for
+/// other IDs with roughly the configured false positive rate once the expected
+/// number of IDs have been inserted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdBloom {
+    words: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl IdBloom {
+    /// An empty filter sized for `expected_items` IDs at `false_positive_rate`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `false_positive_rate` is not between 0 and 1 (exclusive).
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "false positive rate must be between 0 and 1, got {}",
+            false_positive_rate
+        );
+        let n = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = ((-n * false_positive_rate.ln()) / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / n) * ln2).round().max(1.0) as u32;
+        IdBloom {
+            words: vec![0; words_for(num_bits) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    pub fn builder() -> IdBloomBuilder {
+        IdBloomBuilder::default()
+    }
+
+    fn bit_indexes(&self, id: &ID) -> impl Iterator<Item = u64> {
+        let (h1, h2) = hash_pair(id);
+        let num_bits = self.num_bits;
+        (0..u64::from(self.num_hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    pub fn insert(&mut self, id: &ID) {
+        let bits = self.bit_indexes(id);
+        for bit in bits {
+            self.words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// False if `id` was definitely never inserted
+    pub fn maybe_contains(&self, id: &ID) -> bool {
+        self.bit_indexes(id)
+            .all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Encode the filter for caching, see [`IdBloom::from_bytes`]
+    ///
+    /// Layout (little endian): number of hashes as u32, number of bits as u64,
+    /// then the bit words as u64s.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + self.words.len() * 8);
+        bytes.extend_from_slice(&self.num_hashes.to_le_bytes());
+        bytes.extend_from_slice(&self.num_bits.to_le_bytes());
+        for word in &self.words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decode a filter produced by [`IdBloom::to_bytes`], `None` if the bytes are malformed
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 12 {
+            return None;
+        }
+        let (header, body) = bytes.split_at(12);
+        let mut num_hashes = [0; 4];
+        num_hashes.copy_from_slice(&header[..4]);
+        let num_hashes = u32::from_le_bytes(num_hashes);
+        let mut num_bits = [0; 8];
+        num_bits.copy_from_slice(&header[4..]);
+        let num_bits = u64::from_le_bytes(num_bits);
+        if num_hashes == 0 || num_bits == 0 || body.len() as u64 != words_for(num_bits) * 8 {
+            return None;
+        }
+        let words = body
+            .chunks(8)
+            .map(|chunk| {
+                let mut word = [0; 8];
+                word.copy_from_slice(chunk);
+                u64::from_le_bytes(word)
+            })
+            .collect();
+        Some(IdBloom {
+            words,
+            num_bits,
+            num_hashes,
+        })
+    }
+}
+
+/// Builds an [`IdBloom`] from a collection of IDs
+#[derive(Clone, Debug)]
+pub struct IdBloomBuilder {
+    expected_items: Option<usize>,
+    false_positive_rate: f64,
+}
+
+impl Default for IdBloomBuilder {
+    fn default() -> Self {
+        IdBloomBuilder {
+            expected_items: None,
+            false_positive_rate: 0.01,
+        }
+    }
+}
+
+impl IdBloomBuilder {
+    /// Target false positive rate, defaults to 1%
+    pub fn false_positive_rate(mut self, rate: f64) -> Self {
+        self.false_positive_rate = rate;
+        self
+    }
+
+    /// Size the filter for this many IDs instead of the number passed to `build`
+    ///
+    /// Set this when more IDs will be inserted later.
+    pub fn expected_items(mut self, expected_items: usize) -> Self {
+        self.expected_items = Some(expected_items);
+        self
+    }
+
+    /// Build a filter containing `ids`
+    ///
+    /// Without `expected_items` the IDs are buffered first to count them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the false positive rate is not between 0 and 1 (exclusive).
+    pub fn build<I>(self, ids: I) -> IdBloom
+    where
+        I: IntoIterator,
+        I::Item: Borrow<ID>,
+    {
+        match self.expected_items {
+            Some(expected_items) => {
+                let mut bloom = IdBloom::new(expected_items, self.false_positive_rate);
+                for id in ids {
+                    bloom.insert(id.borrow());
+                }
+                bloom
+            }
+            None => {
+                let ids: Vec<I::Item> = ids.into_iter().collect();
+                self.expected_items(ids.len()).build(ids)
+            }
+        }
+    }
+}
+
+/// Number of 64-bit words holding `num_bits` bits
+fn words_for(num_bits: u64) -> u64 {
+    num_bits / 64 + u64::from(num_bits % 64 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_pair_is_stable() {
+        // serialized filters depend on these
+        assert_eq!(hash_pair(&ID::with_i64(42)).0, 0x21fd_d471_1908_3f4f);
+        assert_eq!(
+            hash_pair(&ID::with_string("users/1")).0,
+            0x071a_cb12_442f_1450
+        );
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let ids: Vec<ID> = (0..1000).map(ID::with_i64).collect();
+        let bloom = IdBloom::builder().false_positive_rate(0.01).build(&ids);
+        assert!(ids.iter().all(|id| bloom.maybe_contains(id)));
+        assert!(!IdBloom::new(10, 0.01).maybe_contains(&ID::with_i64(1)));
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let bloom = IdBloom::builder()
+            .false_positive_rate(0.01)
+            .build((0..10_000).map(ID::with_i64));
+        let false_positives = (10_000..20_000)
+            .map(ID::with_i64)
+            .filter(|id| bloom.maybe_contains(id))
+            .count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let bloom = IdBloom::builder().build(vec![ID::with_string("a"), ID::with_i64(1)]);
+        let restored = IdBloom::from_bytes(&bloom.to_bytes()).unwrap();
+        assert_eq!(restored, bloom);
+        assert!(restored.maybe_contains(&ID::with_string("a")));
+        assert_eq!(IdBloom::from_bytes(&[1, 2, 3]), None);
+        let bytes = bloom.to_bytes();
+        assert_eq!(IdBloom::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Parsing many IDs at once, collecting failures instead of stopping
+
+use crate::{ParseIdError, ID};
+#[cfg(feature = "redact-debug")]
+use std::fmt;
+use std::thread;
+
+/// An input that failed [`ID::parse_strict`] during a bulk parse
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(not(feature = "redact-debug"), derive(Debug))]
+pub struct ParseFailure {
+    /// Position of the input, counting from 0
+    pub index: usize,
+    pub input: String,
+    pub error: ParseIdError,
+}
+
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for ParseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseFailure")
+            .field("index", &self.index)
+            .field("input", &crate::redact::RedactedStr(&self.input))
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+/// Strictly parse every input, collecting the IDs and the failures separately
+///
+/// IDs keep the relative order of their inputs.
+pub fn parse<'a, I>(lines: I) -> (Vec<ID>, Vec<ParseFailure>)
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut ids = Vec::new();
+    let mut failures = Vec::new();
+    for (index, line) in lines.into_iter().enumerate() {
+        match ID::parse_strict(line) {
+            Ok(id) => ids.push(id),
+            Err(error) => failures.push(ParseFailure {
+                index,
+                input: line.to_string(),
+                error,
+            }),
+        }
+    }
+    (ids, failures)
+}
+
+/// [`parse`] on one thread per available CPU, each taking a contiguous chunk
+///
+/// Returns exactly what `parse` would, in the same order. Worth it for
+/// millions of lines; below that, spawning the threads costs more than it saves.
+pub fn parse_par(lines: &[&str]) -> (Vec<ID>, Vec<ParseFailure>) {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = ((lines.len() + threads - 1) / threads).max(1);
+    let chunks: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = lines
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || parse(chunk.iter().copied())))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let mut ids = Vec::with_capacity(lines.len());
+    let mut failures = Vec::new();
+    for (offset, (chunk_ids, chunk_failures)) in (0..).step_by(chunk_size).zip(chunks) {
+        ids.extend(chunk_ids);
+        failures.extend(chunk_failures.into_iter().map(|mut failure| {
+            failure.index += offset;
+            failure
+        }));
+    }
+    (ids, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (ids, failures) = parse("a\n\n$oid:zz\nb".lines());
+        assert_eq!(ids, vec![ID::with_string("a"), ID::with_string("b")]);
+        assert_eq!(
+            failures,
+            vec![
+                ParseFailure {
+                    index: 1,
+                    input: String::new(),
+                    error: ParseIdError::Empty,
+                },
+                ParseFailure {
+                    index: 2,
+                    input: "$oid:zz".to_string(),
+                    error: ParseIdError::InvalidObjectId,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_par() {
+        let lines: Vec<String> = (0..10_000)
+            .map(|i| match i % 7 {
+                0 => String::new(),
+                1 => format!("$oid:{:024x}", i),
+                2 => "$oid:zz".to_string(),
+                _ => i.to_string(),
+            })
+            .collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        assert_eq!(parse_par(&lines), parse(lines.iter().copied()));
+        assert_eq!(parse_par(&[]), (vec![], vec![]));
+        assert_eq!(parse_par(&["a"]).0, vec![ID::with_string("a")]);
+    }
+}
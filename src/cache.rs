@@ -0,0 +1,135 @@
+//! A bounded LRU cache keyed by `ID`, with optional expiry
+
+use crate::ID;
+use lru_cache::LruCache;
+use std::time::{Duration, Instant};
+
+/// The form a cache key is stored under, so `"$oid:..."` strings and the
+/// equivalent ObjectId share an entry
+fn cache_key(id: &ID) -> ID {
+    id.clone().normalize()
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    inserted: Instant,
+}
+
+/// A least recently used cache keyed by ID, with optional expiry
+///
+/// Keys are normalized before lookup, so a String ID holding a canonical
+/// ObjectId string (`"$oid:..."`) finds the entry stored for that ObjectId.
+#[derive(Debug)]
+pub struct IdCache<V> {
+    entries: LruCache<ID, Entry<V>>,
+    ttl: Option<Duration>,
+}
+
+impl<V> IdCache<V> {
+    /// A cache holding at most `capacity` entries that never expire
+    pub fn new(capacity: usize) -> Self {
+        IdCache {
+            entries: LruCache::new(capacity),
+            ttl: None,
+        }
+    }
+
+    /// A cache holding at most `capacity` entries, each expiring `ttl` after insertion
+    pub fn with_ttl(capacity: usize, ttl: Duration) -> Self {
+        IdCache {
+            entries: LruCache::new(capacity),
+            ttl: Some(ttl),
+        }
+    }
+
+    /// Insert `value`, evicting the least recently used entry when full
+    ///
+    /// Returns the previous value for `id`, if any.
+    pub fn insert(&mut self, id: &ID, value: V) -> Option<V> {
+        let entry = Entry {
+            value,
+            inserted: Instant::now(),
+        };
+        self.entries
+            .insert(cache_key(id), entry)
+            .map(|entry| entry.value)
+    }
+
+    /// The unexpired value for `id`, marking it as recently used
+    pub fn get(&mut self, id: &ID) -> Option<&V> {
+        let key = cache_key(id);
+        let ttl = self.ttl;
+        let expired = match self.entries.get_mut(&key) {
+            Some(entry) => ttl.is_some_and(|ttl| entry.inserted.elapsed() >= ttl),
+            None => return None,
+        };
+        if expired {
+            self.entries.remove(&key);
+            return None;
+        }
+        self.entries.get_mut(&key).map(|entry| &entry.value)
+    }
+
+    pub fn remove(&mut self, id: &ID) -> Option<V> {
+        self.entries.remove(&cache_key(id)).map(|entry| entry.value)
+    }
+
+    /// Number of entries, including expired ones not yet evicted
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_canonical_keys() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let mut cache = IdCache::new(2);
+        cache.insert(&ID::with_oid(oid), "user");
+        assert_eq!(
+            cache.get(&ID::with_string("$oid:5eaefffa00c9fdf000c46fdc")),
+            Some(&"user")
+        );
+        assert_eq!(
+            cache.get(&ID::with_string("5eaefffa00c9fdf000c46fdc")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = IdCache::new(2);
+        cache.insert(&ID::with_i64(1), 1);
+        cache.insert(&ID::with_i64(2), 2);
+        cache.get(&ID::with_i64(1));
+        cache.insert(&ID::with_i64(3), 3);
+        assert_eq!(cache.get(&ID::with_i64(2)), None);
+        assert_eq!(cache.get(&ID::with_i64(1)), Some(&1));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_ttl() {
+        let mut cache = IdCache::with_ttl(2, Duration::from_secs(0));
+        cache.insert(&ID::with_i64(1), 1);
+        assert_eq!(cache.get(&ID::with_i64(1)), None);
+        assert!(cache.is_empty());
+
+        let mut cache = IdCache::with_ttl(2, Duration::from_secs(60));
+        cache.insert(&ID::with_i64(1), 1);
+        assert_eq!(cache.get(&ID::with_i64(1)), Some(&1));
+    }
+}
@@ -0,0 +1,47 @@
+//! Change stream filters and `documentKey` lookups by `_id`
+
+use crate::ID;
+use bson::{doc, Document};
+use std::convert::TryFrom;
+
+/// `$match` stage limiting a change stream to events on documents whose `_id` is
+/// any of `ids`
+pub fn match_document_keys(ids: &[ID]) -> Document {
+    doc! { "$match": { "documentKey._id": { "$in": ID::many_to_bson(ids) } } }
+}
+
+/// Extract the `_id` from the `documentKey` of a raw change stream event
+///
+/// 32-bit integer keys are widened to `ID::Int64`. Returns `None` when the event
+/// has no `documentKey._id` or it uses a BSON type `ID` can't represent.
+pub fn document_key_id(event: &Document) -> Option<ID> {
+    ID::try_from(event.get_document("documentKey").ok()?.get("_id")?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_match_document_keys() {
+        assert_eq!(
+            match_document_keys(&[ID::with_string("a")]),
+            doc! { "$match": { "documentKey._id": { "$in": ["a"] } } }
+        );
+    }
+
+    #[test]
+    fn test_document_key_id() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let event = doc! { "operationType": "insert", "documentKey": { "_id": oid.clone() } };
+        assert_eq!(document_key_id(&event), Some(ID::ObjectId(oid)));
+        let event = doc! { "documentKey": { "_id": 5_i32 } };
+        assert_eq!(document_key_id(&event), Some(ID::Int64(5)));
+        assert_eq!(
+            document_key_id(&doc! { "documentKey": { "_id": 1.5 } }),
+            None
+        );
+        assert_eq!(document_key_id(&doc! { "operationType": "drop" }), None);
+    }
+}
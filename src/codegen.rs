@@ -0,0 +1,141 @@
+//! Type definitions for other languages matching the `ID` wire format
+use crate::SerializeOptions;
+use std::fmt::Write;
+
+/// TypeScript type and runtime guard for the JSON form `options` writes
+///
+/// String IDs are always plain strings. ObjectIds are `{"$oid": "<24 hex
+/// digits>"}`, or strings too with
+/// [`SerializeOptions::object_ids_as_strings`]. Integer IDs are numbers, and
+/// with an integer threshold only up to it, larger ones being
+/// `{"$numberLong": "<digits>"}`. The guard accepts nothing else.
+pub fn typescript(options: &SerializeOptions) -> String {
+    let mut ts = String::new();
+    let mut variants = Vec::new();
+    if !options.object_id_strings {
+        ts.push_str("export type MongoObjectId = { $oid: string };\n");
+        variants.push("MongoObjectId");
+    }
+    if options.int_threshold.is_some() {
+        ts.push_str("export type MongoLong = { $numberLong: string };\n");
+        variants.push("MongoLong");
+    }
+    variants.extend(&["string", "number"]);
+    let number_check = match options.int_threshold {
+        Some(threshold) => format!(
+            "Number.isInteger(value) && Math.abs(value) <= {}",
+            threshold
+        ),
+        None => "Number.isInteger(value)".to_string(),
+    };
+    write!(
+        ts,
+        r#"export type MongoId = {};
+
+export function isMongoId(value: unknown): value is MongoId {{
+  if (typeof value === "string") {{
+    return true;
+  }}
+  if (typeof value === "number") {{
+    return {};
+  }}
+"#,
+        variants.join(" | "),
+        number_check
+    )
+    .unwrap();
+    if variants.len() > 2 {
+        ts.push_str(
+            r#"  if (typeof value === "object" && value !== null && Object.keys(value).length === 1) {
+"#,
+        );
+        if !options.object_id_strings {
+            write!(
+                ts,
+                r#"    const oid = (value as {{ $oid?: unknown }}).$oid;
+    if (typeof oid === "string") {{
+      return /{}/.test(oid);
+    }}
+"#,
+                OID_HEX_PATTERN
+            )
+            .unwrap();
+        }
+        if options.int_threshold.is_some() {
+            ts.push_str(
+                r#"    const long = (value as { $numberLong?: unknown }).$numberLong;
+    if (typeof long === "string") {
+      return /^-?[0-9]+$/.test(long);
+    }
+"#,
+            );
+        }
+        ts.push_str("  }\n");
+    }
+    ts.push_str("  return false;\n}\n");
+    ts
+}
+
+const OID_HEX_PATTERN: &str = "^[0-9a-fA-F]{24}$";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ID;
+
+    #[test]
+    fn test_typescript_default() {
+        let options = SerializeOptions::new();
+        let ts = typescript(&options);
+        assert!(ts.contains("export type MongoId = MongoObjectId | string | number;"));
+        assert!(ts.contains("/^[0-9a-fA-F]{24}$/.test(oid)"));
+        assert!(ts.contains("return Number.isInteger(value);"));
+        assert!(!ts.contains("$numberLong"));
+
+        let json = serde_json::to_value(ID::from_string("$oid:5f3d6c0b9e2d4c1a2b3c4d5e")).unwrap();
+        let map = json.as_object().unwrap();
+        assert_eq!(map.len(), 1);
+        assert!(map["$oid"].is_string());
+        assert!(serde_json::to_value(options.wrap(&ID::Int64(i64::MAX)))
+            .unwrap()
+            .is_number());
+        assert!(serde_json::to_value(ID::with_string("a"))
+            .unwrap()
+            .is_string());
+    }
+
+    #[test]
+    fn test_typescript_js_safe_ints() {
+        let options = SerializeOptions::new().js_safe_ints();
+        let ts = typescript(&options);
+        assert!(ts.contains("export type MongoId = MongoObjectId | MongoLong | string | number;"));
+        assert!(ts.contains("Number.isInteger(value) && Math.abs(value) <= 9007199254740991"));
+        assert!(ts.contains("/^-?[0-9]+$/.test(long)"));
+        assert!(ts.contains(".test(oid)"));
+
+        let json = serde_json::to_value(options.wrap(&ID::Int64(-(1 << 60)))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "$numberLong": "-1152921504606846976" })
+        );
+    }
+
+    #[test]
+    fn test_typescript_object_id_strings() {
+        let options = SerializeOptions::new().object_ids_as_strings();
+        let ts = typescript(&options);
+        assert!(ts.contains("export type MongoId = string | number;"));
+        assert!(!ts.contains("$oid"));
+        assert!(!ts.contains("typeof value === \"object\""));
+        let oid = ID::from_string("$oid:5f3d6c0b9e2d4c1a2b3c4d5e");
+        assert!(serde_json::to_value(options.wrap(&oid))
+            .unwrap()
+            .is_string());
+
+        let ts = typescript(&options.stringify_ints_above(9));
+        assert!(ts.contains("export type MongoId = MongoLong | string | number;"));
+        assert!(ts.contains("Math.abs(value) <= 9"));
+        assert!(ts.contains(".test(long)"));
+        assert!(!ts.contains("$oid"));
+    }
+}
@@ -0,0 +1,280 @@
+//! `Collection` extensions for finding and deleting documents by `ID`
+
+use crate::{filters, projection, TryFromBsonError, ID};
+use async_trait::async_trait;
+use bson::{doc, Bson, Document};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    error::Result,
+    options::{CountOptions, FindOneAndUpdateOptions, ReturnDocument},
+    results::{DeleteResult, InsertManyResult, InsertOneResult, UpdateResult},
+    Collection,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    error, fmt,
+    fmt::Debug,
+};
+
+/// Error returned by queries that convert `_id` values to `ID`s
+#[derive(Debug)]
+pub enum IdQueryError {
+    /// The query itself failed
+    Query(mongodb::error::Error),
+    /// Every returned value that couldn't be converted to an `ID`
+    Conversion(Vec<TryFromBsonError>),
+}
+
+impl fmt::Display for IdQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdQueryError::Query(e) => write!(f, "_id query failed: {}", e),
+            IdQueryError::Conversion(errors) => {
+                write!(f, "{} _id values were not valid IDs", errors.len())
+            }
+        }
+    }
+}
+
+impl error::Error for IdQueryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IdQueryError::Query(e) => Some(e),
+            IdQueryError::Conversion(errors) => errors.first().map(|e| e as _),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for IdQueryError {
+    fn from(e: mongodb::error::Error) -> Self {
+        IdQueryError::Query(e)
+    }
+}
+
+/// ID-based CRUD helpers for a mongodb `Collection`
+#[async_trait]
+pub trait CollectionIdExt<T> {
+    /// Find the document with the given `_id`
+    async fn find_by_id(&self, id: &ID) -> Result<Option<T>>;
+
+    /// Delete the document with the given `_id`
+    async fn delete_by_id(&self, id: &ID) -> Result<DeleteResult>;
+
+    /// Replace the document with the given `_id`
+    async fn replace_by_id(&self, id: &ID, replacement: T) -> Result<UpdateResult>;
+
+    /// Check whether a document with the given `_id` exists
+    async fn exists(&self, id: &ID) -> Result<bool>;
+
+    /// Find all documents whose `_id` is in `ids`, keyed by their ID
+    ///
+    /// IDs that match no document are absent from the returned map.
+    async fn find_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, T>>;
+
+    /// Find all documents whose `_id` is in `ids`, querying `chunk_size` IDs at a time
+    ///
+    /// Use this instead of [`find_by_ids`](CollectionIdExt::find_by_ids) when the
+    /// ID set is too large for a single `$in` filter. With `preserve_order` the
+    /// results follow the order of `ids` (each document appearing once), otherwise
+    /// they are returned in the order the server yields them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    async fn find_by_ids_chunked(
+        &self,
+        ids: &[ID],
+        chunk_size: usize,
+        preserve_order: bool,
+    ) -> Result<Vec<T>>;
+
+    /// All distinct `_id` values of documents matching `filter`
+    ///
+    /// Fails with every value that isn't a valid ID if any are returned.
+    async fn distinct_ids(
+        &self,
+        filter: Option<Document>,
+    ) -> std::result::Result<Vec<ID>, IdQueryError>;
+}
+
+#[async_trait]
+impl<T> CollectionIdExt<T> for Collection<T>
+where
+    T: Serialize + DeserializeOwned + Unpin + Debug + Send + Sync,
+{
+    async fn find_by_id(&self, id: &ID) -> Result<Option<T>> {
+        self.find_one(id.filter(), None).await
+    }
+
+    async fn delete_by_id(&self, id: &ID) -> Result<DeleteResult> {
+        self.delete_one(id.filter(), None).await
+    }
+
+    async fn replace_by_id(&self, id: &ID, replacement: T) -> Result<UpdateResult> {
+        self.replace_one(id.filter(), replacement, None).await
+    }
+
+    async fn exists(&self, id: &ID) -> Result<bool> {
+        let options = CountOptions::builder().limit(1).build();
+        Ok(self.count_documents(id.filter(), options).await? > 0)
+    }
+
+    async fn find_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, T>> {
+        let mut found = HashMap::with_capacity(ids.len());
+        if ids.is_empty() {
+            return Ok(found);
+        }
+        // read raw documents so the `_id` is available regardless of `T`
+        let mut cursor = self
+            .clone_with_type::<Document>()
+            .find(filters::any_of(ids), None)
+            .await?;
+        while let Some(doc) = cursor.try_next().await? {
+            if let Some(Ok(id)) = doc.get("_id").map(ID::try_from) {
+                found.insert(id, bson::from_document(doc)?);
+            }
+        }
+        Ok(found)
+    }
+
+    async fn find_by_ids_chunked(
+        &self,
+        ids: &[ID],
+        chunk_size: usize,
+        preserve_order: bool,
+    ) -> Result<Vec<T>> {
+        if preserve_order {
+            let mut found = HashMap::with_capacity(ids.len());
+            for chunk in ids.chunks(chunk_size) {
+                found.extend(self.find_by_ids(chunk).await?);
+            }
+            return Ok(ids.iter().filter_map(|id| found.remove(id)).collect());
+        }
+        let mut results = Vec::with_capacity(ids.len());
+        for filter in filters::chunked_in(ids, chunk_size) {
+            let mut cursor = self.find(filter, None).await?;
+            while let Some(item) = cursor.try_next().await? {
+                results.push(item);
+            }
+        }
+        Ok(results)
+    }
+
+    async fn distinct_ids(
+        &self,
+        filter: Option<Document>,
+    ) -> std::result::Result<Vec<ID>, IdQueryError> {
+        let values = self.distinct("_id", filter, None).await?;
+        let mut ids = Vec::with_capacity(values.len());
+        let mut errors = Vec::new();
+        for value in values {
+            match ID::try_from(value) {
+                Ok(id) => ids.push(id),
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(ids)
+        } else {
+            Err(IdQueryError::Conversion(errors))
+        }
+    }
+}
+
+/// Find the `_id` of the document matching `natural_key_filter`, inserting
+/// `insert_doc` if there is none
+///
+/// Runs a single upserting `find_one_and_update` with `$setOnInsert`, so unlike
+/// a find followed by an insert it can't create duplicates when the natural key
+/// has a unique index (without one, concurrent upserts may still both insert).
+pub async fn get_or_create_id<T>(
+    collection: &Collection<T>,
+    natural_key_filter: Document,
+    insert_doc: Document,
+) -> std::result::Result<ID, IdQueryError>
+where
+    T: Serialize + DeserializeOwned + Unpin + Debug,
+{
+    let options = FindOneAndUpdateOptions::builder()
+        .upsert(Some(true))
+        .return_document(Some(ReturnDocument::After))
+        .projection(Some(projection::id_only()))
+        .build();
+    let found = collection
+        .clone_with_type::<Document>()
+        .find_one_and_update(
+            natural_key_filter,
+            doc! { "$setOnInsert": insert_doc },
+            options,
+        )
+        .await?;
+    let id = found
+        .and_then(|mut doc| doc.remove("_id"))
+        .unwrap_or(Bson::Null);
+    ID::try_from(id).map_err(|e| IdQueryError::Conversion(vec![e]))
+}
+
+impl ID {
+    /// The `_id` assigned by `insert_one`
+    pub fn from_insert_one(result: &InsertOneResult) -> std::result::Result<ID, TryFromBsonError> {
+        ID::try_from(&result.inserted_id)
+    }
+}
+
+/// The `_id`s assigned by `insert_many`, keyed and ordered by the index of
+/// the inserted document
+pub fn ids_from_insert_many(
+    result: &InsertManyResult,
+) -> std::result::Result<BTreeMap<usize, ID>, IdQueryError> {
+    ids_by_index(&result.inserted_ids)
+}
+
+fn ids_by_index(
+    inserted: &HashMap<usize, Bson>,
+) -> std::result::Result<BTreeMap<usize, ID>, IdQueryError> {
+    let mut ids = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (index, value) in inserted {
+        match ID::try_from(value) {
+            Ok(id) => {
+                ids.insert(*index, id);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(ids)
+    } else {
+        Err(IdQueryError::Conversion(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_ids_by_index() {
+        let oid = ObjectId::new();
+        let mut inserted = HashMap::new();
+        inserted.insert(1, Bson::String("b".to_string()));
+        inserted.insert(0, Bson::ObjectId(oid.clone()));
+        let ids = ids_by_index(&inserted).unwrap();
+        assert_eq!(
+            ids.into_iter().collect::<Vec<_>>(),
+            vec![(0, ID::ObjectId(oid)), (1, ID::with_string("b"))]
+        );
+
+        inserted.insert(2, Bson::Double(1.5));
+        match ids_by_index(&inserted) {
+            Err(IdQueryError::Conversion(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].value(), &Bson::Double(1.5));
+            }
+            other => panic!("expected a conversion error, got {:?}", other),
+        }
+    }
+}
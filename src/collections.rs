@@ -0,0 +1,489 @@
+//! Sets and maps keyed by `ID`
+
+use crate::{filters, map_keys, ID};
+use bson::{Bson, Document};
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    iter::FromIterator,
+    ops::{Deref, DerefMut},
+};
+
+/// A set of IDs backed by a fast non-cryptographic hasher
+///
+/// Serializes as an array of IDs.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct IDSet(FnvHashSet<ID>);
+
+impl IDSet {
+    pub fn new() -> Self {
+        IDSet(FnvHashSet::default())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        IDSet(FnvHashSet::with_capacity_and_hasher(
+            capacity,
+            Default::default(),
+        ))
+    }
+
+    /// Filter matching documents whose `_id` is in this set
+    pub fn to_in_filter(&self) -> Document {
+        filters::in_filter(self.0.iter().map(ID::to_bson))
+    }
+
+    /// IDs in this set that are not in `other`
+    pub fn difference(&self, other: &IDSet) -> IDSet {
+        self.0.difference(&other.0).cloned().collect()
+    }
+
+    /// IDs in both this set and `other`
+    pub fn intersection(&self, other: &IDSet) -> IDSet {
+        self.0.intersection(&other.0).cloned().collect()
+    }
+
+    pub fn into_inner(self) -> FnvHashSet<ID> {
+        self.0
+    }
+}
+
+impl Deref for IDSet {
+    type Target = FnvHashSet<ID>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for IDSet {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromIterator<ID> for IDSet {
+    fn from_iter<I: IntoIterator<Item = ID>>(iter: I) -> Self {
+        IDSet(iter.into_iter().collect())
+    }
+}
+
+impl Extend<ID> for IDSet {
+    fn extend<I: IntoIterator<Item = ID>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for IDSet {
+    type Item = ID;
+    type IntoIter = std::collections::hash_set::IntoIter<ID>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IDSet {
+    type Item = &'a ID;
+    type IntoIter = std::collections::hash_set::Iter<'a, ID>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// A sorted set of Int64 IDs, stored as plain integers
+///
+/// Takes a fraction of the memory of an [`IDSet`] when every ID is an Int64,
+/// and yields the IDs in order. Serializes as an array of integers.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Int64IdSet(BTreeSet<i64>);
+
+impl Int64IdSet {
+    pub fn new() -> Self {
+        Int64IdSet(BTreeSet::new())
+    }
+
+    /// IDs in this set, `other` or both
+    pub fn union(&self, other: &Int64IdSet) -> Int64IdSet {
+        self.0.union(&other.0).copied().collect()
+    }
+
+    /// IDs in this set that are not in `other`
+    pub fn difference(&self, other: &Int64IdSet) -> Int64IdSet {
+        self.0.difference(&other.0).copied().collect()
+    }
+
+    /// IDs in both this set and `other`
+    pub fn intersection(&self, other: &Int64IdSet) -> Int64IdSet {
+        self.0.intersection(&other.0).copied().collect()
+    }
+
+    /// Filter matching documents whose `_id` is in this set
+    pub fn to_in_filter(&self) -> Document {
+        filters::in_filter(self.0.iter().map(|i| Bson::Int64(*i)))
+    }
+
+    /// One `$in` filter per `chunk_size` IDs, in ascending order, as
+    /// [`filters::chunked_in`] does for slices
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn chunked_in(&self, chunk_size: usize) -> impl Iterator<Item = Document> + '_ {
+        assert!(chunk_size > 0, "chunk_size is 0");
+        let mut ids = self.0.iter().peekable();
+        std::iter::from_fn(move || {
+            ids.peek()?;
+            Some(filters::in_filter(
+                ids.by_ref().take(chunk_size).map(|i| Bson::Int64(*i)),
+            ))
+        })
+    }
+
+    pub fn into_inner(self) -> BTreeSet<i64> {
+        self.0
+    }
+}
+
+impl Deref for Int64IdSet {
+    type Target = BTreeSet<i64>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Int64IdSet {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromIterator<i64> for Int64IdSet {
+    fn from_iter<I: IntoIterator<Item = i64>>(iter: I) -> Self {
+        Int64IdSet(iter.into_iter().collect())
+    }
+}
+
+impl Extend<i64> for Int64IdSet {
+    fn extend<I: IntoIterator<Item = i64>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for Int64IdSet {
+    type Item = i64;
+    type IntoIter = std::collections::btree_set::IntoIter<i64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Int64IdSet {
+    type Item = &'a i64;
+    type IntoIter = std::collections::btree_set::Iter<'a, i64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<Int64IdSet> for IDSet {
+    fn from(set: Int64IdSet) -> IDSet {
+        set.0.into_iter().map(ID::Int64).collect()
+    }
+}
+
+/// Fails with the first ID found that isn't an Int64
+impl TryFrom<&IDSet> for Int64IdSet {
+    type Error = ID;
+
+    fn try_from(set: &IDSet) -> Result<Int64IdSet, ID> {
+        set.iter()
+            .map(|id| match id {
+                ID::Int64(i) => Ok(*i),
+                id => Err(id.clone()),
+            })
+            .collect()
+    }
+}
+
+/// A map keyed by ID backed by a fast non-cryptographic hasher
+///
+/// Serializes as an object keyed by strings that keep each ID's variant, see
+/// [`map_keys`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IDMap<V>(FnvHashMap<ID, V>);
+
+impl<V> IDMap<V> {
+    pub fn new() -> Self {
+        IDMap(FnvHashMap::default())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        IDMap(FnvHashMap::with_capacity_and_hasher(
+            capacity,
+            Default::default(),
+        ))
+    }
+
+    /// Filter matching documents whose `_id` is a key of this map
+    pub fn to_in_filter(&self) -> Document {
+        filters::in_filter(self.0.keys().map(ID::to_bson))
+    }
+
+    /// The set of keys of this map
+    pub fn key_set(&self) -> IDSet {
+        self.0.keys().cloned().collect()
+    }
+
+    pub fn into_inner(self) -> FnvHashMap<ID, V> {
+        self.0
+    }
+}
+
+impl<V> Default for IDMap<V> {
+    fn default() -> Self {
+        IDMap::new()
+    }
+}
+
+impl<V> Deref for IDMap<V> {
+    type Target = FnvHashMap<ID, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<V> DerefMut for IDMap<V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<V> FromIterator<(ID, V)> for IDMap<V> {
+    fn from_iter<I: IntoIterator<Item = (ID, V)>>(iter: I) -> Self {
+        IDMap(iter.into_iter().collect())
+    }
+}
+
+impl<V> Extend<(ID, V)> for IDMap<V> {
+    fn extend<I: IntoIterator<Item = (ID, V)>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl<V> IntoIterator for IDMap<V> {
+    type Item = (ID, V);
+    type IntoIter = std::collections::hash_map::IntoIter<ID, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a IDMap<V> {
+    type Item = (&'a ID, &'a V);
+    type IntoIter = std::collections::hash_map::Iter<'a, ID, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<V: Serialize> Serialize for IDMap<V> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        map_keys::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for IDMap<V> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        map_keys::deserialize(deserializer).map(IDMap)
+    }
+}
+
+/// A list of IDs storing up to 4 inline before allocating
+///
+/// Serializes exactly like a `Vec<ID>`.
+#[cfg(feature = "smallvec")]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct IDList(smallvec::SmallVec<[ID; 4]>);
+
+#[cfg(feature = "smallvec")]
+impl IDList {
+    pub fn new() -> Self {
+        IDList(smallvec::SmallVec::new())
+    }
+
+    /// Filter matching documents whose `_id` is in this list
+    pub fn to_in_filter(&self) -> Document {
+        filters::any_of(&self.0)
+    }
+
+    pub fn into_vec(self) -> Vec<ID> {
+        self.0.into_vec()
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl Deref for IDList {
+    type Target = smallvec::SmallVec<[ID; 4]>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl DerefMut for IDList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl From<Vec<ID>> for IDList {
+    fn from(ids: Vec<ID>) -> Self {
+        IDList(ids.into())
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl FromIterator<ID> for IDList {
+    fn from_iter<I: IntoIterator<Item = ID>>(iter: I) -> Self {
+        IDList(iter.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl Extend<ID> for IDList {
+    fn extend<I: IntoIterator<Item = ID>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl IntoIterator for IDList {
+    type Item = ID;
+    type IntoIter = smallvec::IntoIter<[ID; 4]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<'a> IntoIterator for &'a IDList {
+    type Item = &'a ID;
+    type IntoIter = std::slice::Iter<'a, ID>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::{doc, oid::ObjectId};
+
+    #[test]
+    fn test_set_algebra() {
+        let a: IDSet = vec![ID::with_i64(1), ID::with_i64(2)].into_iter().collect();
+        let b: IDSet = vec![ID::with_i64(2), ID::with_i64(3)].into_iter().collect();
+        assert_eq!(
+            a.difference(&b),
+            vec![ID::with_i64(1)].into_iter().collect()
+        );
+        assert_eq!(
+            a.intersection(&b),
+            vec![ID::with_i64(2)].into_iter().collect()
+        );
+        assert_eq!(
+            a.intersection(&b).to_in_filter(),
+            doc! { "_id": { "$in": [2_i64] } }
+        );
+    }
+
+    #[test]
+    fn test_int64_set() {
+        let a: Int64IdSet = vec![3, 1, 2].into_iter().collect();
+        let b: Int64IdSet = vec![2, 3, 4].into_iter().collect();
+        assert_eq!(a.union(&b).into_inner(), (1..=4).collect());
+        assert_eq!(a.difference(&b).into_inner(), [1].iter().copied().collect());
+        assert_eq!(
+            a.intersection(&b).to_in_filter(),
+            doc! { "_id": { "$in": [2_i64, 3_i64] } }
+        );
+
+        let filters: Vec<Document> = a.union(&b).chunked_in(3).collect();
+        assert_eq!(
+            filters,
+            vec![
+                doc! { "_id": { "$in": [1_i64, 2_i64, 3_i64] } },
+                doc! { "_id": { "$in": [4_i64] } },
+            ]
+        );
+        assert_eq!(Int64IdSet::new().chunked_in(3).count(), 0);
+
+        let ids = IDSet::from(a.clone());
+        assert!(ids.contains(&ID::Int64(3)));
+        assert_eq!(Int64IdSet::try_from(&ids), Ok(a));
+        let mixed: IDSet = vec![ID::Int64(1), ID::with_string("a")]
+            .into_iter()
+            .collect();
+        assert_eq!(Int64IdSet::try_from(&mixed), Err(ID::with_string("a")));
+
+        let json = serde_json::to_string(&b).unwrap();
+        assert_eq!(json, "[2,3,4]");
+        assert_eq!(serde_json::from_str::<Int64IdSet>(&json).unwrap(), b);
+    }
+
+    #[test]
+    fn test_set_serde() {
+        let set: IDSet = vec![ID::with_string("a")].into_iter().collect();
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, r#"["a"]"#);
+        assert_eq!(serde_json::from_str::<IDSet>(&json).unwrap(), set);
+    }
+
+    #[test]
+    fn test_map_serde() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let mut map = IDMap::new();
+        map.insert(ID::with_oid(oid), 1);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"$oid:5eaefffa00c9fdf000c46fdc":1}"#);
+        assert_eq!(serde_json::from_str::<IDMap<i32>>(&json).unwrap(), map);
+
+        map.insert(ID::with_i64(1), 2);
+        map.insert(ID::with_string("1"), 3);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::from_str::<IDMap<i32>>(&json).unwrap(), map);
+    }
+
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn test_list_serde() {
+        let ids = vec![ID::with_string("a"), ID::with_i64(2)];
+        let list = IDList::from(ids.clone());
+        assert!(!list.spilled());
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, serde_json::to_string(&ids).unwrap());
+        assert_eq!(serde_json::from_str::<IDList>(&json).unwrap(), list);
+        assert_eq!(bson::to_bson(&list).unwrap(), bson::to_bson(&ids).unwrap());
+    }
+}
@@ -0,0 +1,32 @@
+//! Constant-time comparison of IDs
+
+use crate::ID;
+use subtle::ConstantTimeEq;
+
+impl ID {
+    /// Compare two IDs in time independent of where they differ
+    ///
+    /// Compares the compact byte encodings, so IDs of different variants or
+    /// lengths are unequal; only the length of the encodings can leak through
+    /// timing. Use this when an ID is a secret, e.g. a password reset token.
+    pub fn ct_eq(&self, other: &ID) -> bool {
+        self.to_bytes().ct_eq(&other.to_bytes()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ID;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_ct_eq() {
+        let oid = ObjectId::new();
+        assert!(ID::ObjectId(oid.clone()).ct_eq(&ID::ObjectId(oid)));
+        assert!(ID::with_string("token").ct_eq(&ID::with_string("token")));
+        assert!(!ID::with_string("token").ct_eq(&ID::with_string("tokeN")));
+        assert!(!ID::with_string("token").ct_eq(&ID::with_string("token2")));
+        assert!(!ID::with_string("1").ct_eq(&ID::Int64(1)));
+        assert!(ID::Int64(-1).ct_eq(&ID::Int64(-1)));
+    }
+}
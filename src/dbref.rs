@@ -0,0 +1,134 @@
+//! Typed DBRefs pointing at documents by `ID`
+
+use crate::ID;
+use serde::{Deserialize, Serialize};
+
+/// A MongoDB database reference
+///
+/// Serializes using the DBRef convention: `{"$ref": ..., "$id": ..., "$db": ...}`,
+/// omitting `$db` when it is not set.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct DbRef {
+    /// Name of the collection holding the referenced document
+    #[serde(rename = "$ref")]
+    pub collection: String,
+    /// `_id` of the referenced document
+    #[serde(rename = "$id")]
+    pub id: ID,
+    /// Name of the database holding the collection, if not the current one
+    #[serde(rename = "$db", default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+}
+
+impl DbRef {
+    /// Reference a document in `collection` of the current database
+    pub fn new<S: Into<String>>(collection: S, id: ID) -> Self {
+        DbRef {
+            collection: collection.into(),
+            id,
+            database: None,
+        }
+    }
+
+    /// Reference a document in `collection` of another database
+    pub fn with_database<S: Into<String>, D: Into<String>>(
+        collection: S,
+        id: ID,
+        database: D,
+    ) -> Self {
+        DbRef {
+            collection: collection.into(),
+            id,
+            database: Some(database.into()),
+        }
+    }
+}
+
+#[cfg(feature = "mongodb")]
+pub use self::resolve::DbRefResolver;
+
+#[cfg(feature = "mongodb")]
+mod resolve {
+    use super::DbRef;
+    use async_trait::async_trait;
+    use bson::Document;
+    use mongodb::{error::Result, Client, Database};
+    use serde::de::DeserializeOwned;
+
+    /// Loads the documents `DbRef`s point to
+    #[async_trait]
+    pub trait DbRefResolver {
+        /// Load the referenced document, or `None` if it doesn't exist or lives in
+        /// a database this resolver can't reach
+        async fn resolve<T>(&self, dbref: &DbRef) -> Result<Option<T>>
+        where
+            T: DeserializeOwned;
+    }
+
+    async fn find_in<T: DeserializeOwned>(db: &Database, dbref: &DbRef) -> Result<Option<T>> {
+        let found = db
+            .collection_with_type::<Document>(&dbref.collection)
+            .find_one(dbref.id.filter(), None)
+            .await?;
+        Ok(match found {
+            Some(doc) => Some(bson::from_document(doc)?),
+            None => None,
+        })
+    }
+
+    /// Resolves references in this database, those naming another `$db` resolve to `None`
+    #[async_trait]
+    impl DbRefResolver for Database {
+        async fn resolve<T>(&self, dbref: &DbRef) -> Result<Option<T>>
+        where
+            T: DeserializeOwned,
+        {
+            match &dbref.database {
+                Some(db) if db != self.name() => Ok(None),
+                _ => find_in(self, dbref).await,
+            }
+        }
+    }
+
+    /// Resolves references in any database, those without a `$db` resolve to `None`
+    #[async_trait]
+    impl DbRefResolver for Client {
+        async fn resolve<T>(&self, dbref: &DbRef) -> Result<Option<T>>
+        where
+            T: DeserializeOwned,
+        {
+            match &dbref.database {
+                Some(db) => find_in(&self.database(db), dbref).await,
+                None => Ok(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::{doc, oid::ObjectId, Bson};
+
+    #[test]
+    fn test_dbref_json() {
+        let dbref = DbRef::new("users", ID::with_string("abc"));
+        let json = serde_json::to_string(&dbref).unwrap();
+        assert_eq!(json, r#"{"$ref":"users","$id":"abc"}"#);
+        assert_eq!(serde_json::from_str::<DbRef>(&json).unwrap(), dbref);
+
+        let dbref = DbRef::with_database("users", ID::with_i64(3), "accounts");
+        let json = serde_json::to_string(&dbref).unwrap();
+        assert_eq!(json, r#"{"$ref":"users","$id":3,"$db":"accounts"}"#);
+        assert_eq!(serde_json::from_str::<DbRef>(&json).unwrap(), dbref);
+    }
+
+    #[test]
+    fn test_dbref_bson() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let dbref = DbRef::new("users", ID::with_oid(oid.clone()));
+        let bson = bson::to_bson(&dbref).unwrap();
+        assert_eq!(bson, Bson::Document(doc! { "$ref": "users", "$id": oid }));
+        assert_eq!(bson::from_bson::<DbRef>(bson).unwrap(), dbref);
+    }
+}
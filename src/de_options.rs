@@ -0,0 +1,588 @@
+//! Configurable deserialization of IDs
+
+use crate::{oid_hex, pipeline::IdType, IDVisitor, ID};
+use bson::{Bson, Document};
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::fmt;
+
+/// Limits applied while deserializing an ID from untrusted input
+///
+/// The default has no limits, matching `ID`'s `Deserialize` impl. Floats,
+/// including NaN and infinities, are always rejected.
+///
+/// ```
+/// use mongodb_id::DeserializeOptions;
+///
+/// let options = DeserializeOptions::new().max_string_len(64).max_depth(2);
+/// let mut json = serde_json::Deserializer::from_str(r#"{"a": {"b": {"c": 1}}}"#);
+/// assert!(options.deserialize(&mut json).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DeserializeOptions {
+    pub(crate) max_string_len: Option<usize>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) strict_integers: bool,
+    pub(crate) object_id_strings: ObjectIdStrings,
+    pub(crate) strict_maps: bool,
+    pub(crate) legacy_ejson: bool,
+    /// Indexed by `IdType as usize`
+    allowed_types: [bool; 3],
+}
+
+impl Default for DeserializeOptions {
+    fn default() -> Self {
+        DeserializeOptions::DEFAULT
+    }
+}
+
+/// Deserializes an ID with the given options, e.g. as an element of a
+/// sequence through `SeqAccess::next_element_seed`
+///
+/// ```
+/// use mongodb_id::{pipeline::IdType, DeserializeOptions, IdSeed};
+/// use serde::de::DeserializeSeed;
+///
+/// let seed = IdSeed {
+///     options: DeserializeOptions::new().allow_types(&[IdType::Int64]),
+/// };
+/// let mut json = serde_json::Deserializer::from_str(r#""a""#);
+/// assert!(seed.deserialize(&mut json).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IdSeed {
+    pub options: DeserializeOptions,
+}
+
+impl From<DeserializeOptions> for IdSeed {
+    fn from(options: DeserializeOptions) -> Self {
+        IdSeed { options }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for IdSeed {
+    type Value = ID;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<ID, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.options.deserialize(deserializer)
+    }
+}
+
+/// Keys of the extended JSON maps an ID can be written as
+const ID_MAP_KEYS: [&str; 3] = ["$oid", "$numberLong", "$numberInt"];
+
+/// Which strings deserialize as ObjectIds, set with
+/// [`DeserializeOptions::object_id_strings`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ObjectIdStrings {
+    /// Every string is kept as a String ID
+    Never,
+    /// `$oid:` followed by 24 hex digits, as [`ID::from_string`] detects
+    #[default]
+    Canonical,
+    /// Also with surrounding whitespace, any case of the `$oid:` prefix, or
+    /// just the 24 hex digits
+    Lenient,
+}
+
+impl DeserializeOptions {
+    pub(crate) const DEFAULT: DeserializeOptions = DeserializeOptions {
+        max_string_len: None,
+        max_depth: None,
+        strict_integers: false,
+        object_id_strings: ObjectIdStrings::Canonical,
+        strict_maps: false,
+        legacy_ejson: cfg!(feature = "legacy-ejson"),
+        allowed_types: [true; 3],
+    };
+
+    pub fn new() -> Self {
+        DeserializeOptions::default()
+    }
+
+    /// Only accept maps with exactly one of the extended JSON keys an ID can
+    /// be written with, `$oid`, `$numberLong` and `$numberInt`
+    ///
+    /// Otherwise unknown keys make the map fail to convert, but a repeated key
+    /// silently replaces the earlier value. To apply it to a field:
+    ///
+    /// ```
+    /// use mongodb_id::{DeserializeOptions, ID};
+    /// use serde::{Deserialize, Deserializer};
+    ///
+    /// fn strict<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ID, D::Error> {
+    ///     DeserializeOptions::new().strict_maps(true).deserialize(deserializer)
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Order {
+    ///     #[serde(deserialize_with = "strict")]
+    ///     id: ID,
+    /// }
+    ///
+    /// let json = r#"{"id": {"$numberLong": "5", "$numberLong": "6"}}"#;
+    /// assert!(serde_json::from_str::<Order>(json).is_err());
+    /// ```
+    pub fn strict_maps(mut self, strict: bool) -> Self {
+        self.strict_maps = strict;
+        self
+    }
+
+    /// Reject strings longer than `max` bytes, including those inside maps
+    ///
+    /// Borrowed strings are checked before the ID copies them; whether the
+    /// input was already buffered depends on the deserializer.
+    pub fn max_string_len(mut self, max: usize) -> Self {
+        self.max_string_len = Some(max);
+        self
+    }
+
+    /// Reject maps and arrays nested deeper than `max`, counting the outer map
+    /// as depth 1
+    ///
+    /// `{"$oid": ...}` needs a depth of 1 and `{"$numberLong": ...}` in relaxed
+    /// extended JSON inside another map needs 2.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = Some(max);
+        self
+    }
+
+    /// Reject unsigned integers above `i64::MAX` instead of wrapping them
+    pub fn strict_integers(mut self, strict: bool) -> Self {
+        self.strict_integers = strict;
+        self
+    }
+
+    /// Also accept the legacy (v1) extended JSON forms of IDs
+    ///
+    /// Only `{"$numberLong": 5}`, with a number rather than a string, differs
+    /// from the current spec for the types an ID can hold. On by default with
+    /// the `legacy-ejson` feature, which also applies it to `ID`'s
+    /// `Deserialize` impl.
+    pub fn legacy_ejson(mut self, legacy: bool) -> Self {
+        self.legacy_ejson = legacy;
+        self
+    }
+
+    /// Which strings become ObjectId IDs rather than String IDs
+    ///
+    /// `{"$oid": ...}` maps are always ObjectIds.
+    pub fn object_id_strings(mut self, mode: ObjectIdStrings) -> Self {
+        self.object_id_strings = mode;
+        self
+    }
+
+    /// Only accept IDs of these variants
+    pub fn allow_types(mut self, types: &[IdType]) -> Self {
+        self.allowed_types = [false; 3];
+        for t in types {
+            self.allowed_types[*t as usize] = true;
+        }
+        self
+    }
+
+    /// Deserialize an ID applying these options
+    pub fn deserialize<'de, D>(&self, deserializer: D) -> Result<ID, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = deserializer.deserialize_any(IDVisitor(self))?;
+        if self.allowed_types[IdType::from(&id) as usize] {
+            Ok(id)
+        } else {
+            Err(de::Error::custom(format_args!(
+                "{:?} IDs are not allowed",
+                IdType::from(&id)
+            )))
+        }
+    }
+
+    pub(crate) fn check_len<E: de::Error>(&self, len: usize) -> Result<(), E> {
+        match self.max_string_len {
+            Some(max) if len > max => Err(E::custom(format_args!(
+                "ID string is {} bytes long, the maximum is {}",
+                len, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn id_from_string(&self, s: String) -> ID {
+        match self.object_id_strings {
+            ObjectIdStrings::Never => ID::String(s),
+            ObjectIdStrings::Canonical => ID::from_string(s),
+            ObjectIdStrings::Lenient => match lenient_oid(&s) {
+                Some(oid) => ID::ObjectId(oid),
+                None => ID::String(s),
+            },
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.max_string_len.is_none()
+            && self.max_depth.is_none()
+            && !self.strict_maps
+            && !self.legacy_ejson
+    }
+}
+
+fn lenient_oid(s: &str) -> Option<bson::oid::ObjectId> {
+    let s = s.trim();
+    let hex = match s.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("$oid:") => &s[5..],
+        _ => s,
+    };
+    oid_hex::parse_oid(hex)
+}
+
+/// Read the map an ID was given as into `Bson`, converting extended JSON
+pub(crate) fn bson_from_map<'de, M>(
+    access: M,
+    options: &DeserializeOptions,
+) -> Result<Bson, M::Error>
+where
+    M: MapAccess<'de>,
+{
+    if options.is_unlimited() {
+        return Bson::deserialize(de::value::MapAccessDeserializer::new(access));
+    }
+    let doc = Limited { options, depth: 1 }.visit_map(access)?;
+    if options.legacy_ejson {
+        if let Some(legacy) = legacy_number_long(&doc) {
+            return Ok(legacy);
+        }
+    }
+    // rerun through Bson's own visitor for its extended JSON handling
+    Bson::deserialize(bson::Deserializer::new(doc)).map_err(de::Error::custom)
+}
+
+/// `{"$numberLong": <number>}` as v1 extended JSON wrote it
+fn legacy_number_long(doc: &Bson) -> Option<Bson> {
+    let doc = doc.as_document().filter(|doc| doc.len() == 1)?;
+    match doc.get("$numberLong")? {
+        Bson::Int32(i) => Some(Bson::Int64(i64::from(*i))),
+        Bson::Int64(i) => Some(Bson::Int64(*i)),
+        _ => None,
+    }
+}
+
+/// Reads a value into `Bson` without extended JSON handling, enforcing limits
+#[derive(Clone, Copy)]
+struct Limited<'a> {
+    options: &'a DeserializeOptions,
+    depth: usize,
+}
+
+impl Limited<'_> {
+    /// Check the map or array being visited is within the depth limit
+    fn check_depth<E: de::Error>(self) -> Result<Self, E> {
+        match self.options.max_depth {
+            Some(max) if self.depth > max => Err(E::custom(format_args!(
+                "ID nested deeper than {} levels",
+                max
+            ))),
+            _ => Ok(Limited {
+                depth: self.depth + 1,
+                ..self
+            }),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Limited<'_> {
+    type Value = Bson;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Bson, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Limited<'_> {
+    type Value = Bson;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a BSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Bson, E> {
+        Ok(Bson::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Bson, E> {
+        Ok(Bson::Int64(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Bson, E> {
+        if v > i64::MAX as u64 {
+            return Err(E::invalid_value(de::Unexpected::Unsigned(v), &self));
+        }
+        Ok(Bson::Int64(v as i64))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Bson, E> {
+        Ok(Bson::Double(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Bson, E> {
+        self.options.check_len(v.len())?;
+        Ok(Bson::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Bson, E> {
+        self.options.check_len(v.len())?;
+        Ok(Bson::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Bson, E> {
+        Ok(Bson::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Bson, E> {
+        Ok(Bson::Null)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Bson, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let inner = self.check_depth()?;
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element_seed(inner)? {
+            values.push(value);
+        }
+        Ok(Bson::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Bson, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let inner = self.check_depth()?;
+        let strict = self.options.strict_maps;
+        let mut doc = Document::new();
+        while let Some(key) = map.next_key::<String>()? {
+            self.options.check_len(key.len())?;
+            if strict && self.depth == 1 && !ID_MAP_KEYS.contains(&key.as_str()) {
+                return Err(de::Error::unknown_field(&key, &ID_MAP_KEYS));
+            }
+            if strict && doc.contains_key(&key) {
+                return Err(de::Error::duplicate_field(
+                    ID_MAP_KEYS
+                        .iter()
+                        .find(|k| **k == key)
+                        .copied()
+                        .unwrap_or("key"),
+                ));
+            }
+            doc.insert(key, map.next_value_seed(inner)?);
+        }
+        if strict && self.depth == 1 && doc.len() != 1 {
+            return Err(de::Error::invalid_length(doc.len(), &"a map with one key"));
+        }
+        Ok(Bson::Document(doc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(options: DeserializeOptions, json: &str) -> Result<ID, serde_json::Error> {
+        options.deserialize(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = DeserializeOptions::new().max_string_len(12).max_depth(2);
+        assert_eq!(
+            parse(options, r#""short""#).unwrap(),
+            ID::with_string("short")
+        );
+        assert!(parse(options, r#""far too long!""#).is_err());
+        assert!(parse(options, r#"{"$oid": "5eaefffa00c9fdf000c46fdc"}"#).is_err());
+        assert_eq!(
+            parse(options, r#"{"$numberLong": "5"}"#).unwrap(),
+            ID::Int64(5)
+        );
+        assert!(parse(options, r#"{"a": {"b": {"c": 1}}}"#).is_err());
+        assert!(parse(options, r#"{"a": [[1]]}"#).is_err());
+
+        let options = DeserializeOptions::new().max_string_len(24);
+        assert_eq!(
+            parse(options, r#"{"$oid": "5eaefffa00c9fdf000c46fdc"}"#).unwrap(),
+            ID::from_string("$oid:5eaefffa00c9fdf000c46fdc")
+        );
+    }
+
+    #[test]
+    fn test_depth() {
+        let oid = r#"{"$oid": "5eaefffa00c9fdf000c46fdc"}"#;
+        let depth = |max| DeserializeOptions::new().max_depth(max);
+        assert!(matches!(parse(depth(1), oid).unwrap(), ID::ObjectId(_)));
+        assert!(parse(depth(0), oid).is_err());
+        assert!(parse(depth(1), r#"{"a": {"b": 1}}"#).is_err());
+        assert!(parse(depth(1), r#"{"a": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_seed() {
+        struct Ids(IdSeed);
+        impl<'de> DeserializeSeed<'de> for Ids {
+            type Value = Vec<ID>;
+
+            fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Vec<ID>, D::Error> {
+                d.deserialize_seq(self)
+            }
+        }
+        impl<'de> Visitor<'de> for Ids {
+            type Value = Vec<ID>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("IDs")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<ID>, A::Error> {
+                let mut ids = Vec::new();
+                while let Some(id) = seq.next_element_seed(self.0)? {
+                    ids.push(id);
+                }
+                Ok(ids)
+            }
+        }
+
+        let seed = IdSeed::from(
+            DeserializeOptions::new()
+                .allow_types(&[IdType::ObjectId, IdType::String])
+                .object_id_strings(ObjectIdStrings::Lenient),
+        );
+        let mut json = serde_json::Deserializer::from_str(r#"["5eaefffa00c9fdf000c46fdc", "a"]"#);
+        let ids = Ids(seed).deserialize(&mut json).unwrap();
+        assert!(ids[0].is_object_id());
+        assert_eq!(ids[1], ID::with_string("a"));
+
+        let mut json = serde_json::Deserializer::from_str(r#"["a", 1]"#);
+        let e = Ids(seed).deserialize(&mut json).unwrap_err();
+        assert!(
+            e.to_string().starts_with("Int64 IDs are not allowed"),
+            "{}",
+            e
+        );
+        assert!(IdSeed::default()
+            .deserialize(&mut serde_json::Deserializer::from_str("1"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert!(parse(DeserializeOptions::new(), "1.5").is_err());
+        assert_eq!(
+            parse(DeserializeOptions::new(), "18446744073709551615").unwrap(),
+            ID::Int64(-1)
+        );
+        let strict = DeserializeOptions::new().strict_integers(true);
+        assert!(parse(strict, "18446744073709551615").is_err());
+        assert_eq!(
+            parse(strict, "9223372036854775807").unwrap(),
+            ID::Int64(i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_strict_maps() {
+        let strict = DeserializeOptions::new().strict_maps(true);
+        let oid = r#"{"$oid": "5eaefffa00c9fdf000c46fdc"}"#;
+        assert_eq!(
+            parse(strict, oid).unwrap(),
+            ID::from_string("$oid:5eaefffa00c9fdf000c46fdc")
+        );
+        assert_eq!(
+            parse(strict, r#"{"$numberLong": "5"}"#).unwrap(),
+            ID::Int64(5)
+        );
+        assert_eq!(
+            parse(strict, r#"{"$numberInt": "5"}"#).unwrap(),
+            ID::Int64(5)
+        );
+
+        let duplicate = r#"{"$numberLong": "5", "$numberLong": "6"}"#;
+        assert_eq!(
+            parse(DeserializeOptions::new().strict_maps(false), duplicate).unwrap(),
+            ID::Int64(6)
+        );
+        let e = parse(strict, duplicate).unwrap_err().to_string();
+        assert!(e.contains("duplicate field `$numberLong`"), "{}", e);
+        let e = parse(strict, r#"{"$oid": "5eaefffa00c9fdf000c46fdc", "x": 1}"#).unwrap_err();
+        assert!(e.to_string().contains("unknown field `x`"), "{}", e);
+        assert!(parse(strict, "{}").is_err());
+        assert!(parse(
+            strict,
+            r#"{"$oid": "5eaefffa00c9fdf000c46fdc", "$numberLong": "5"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_legacy_ejson() {
+        let legacy = DeserializeOptions::new().legacy_ejson(true);
+        let current = DeserializeOptions::new().legacy_ejson(false);
+        assert_eq!(
+            parse(legacy, r#"{"$numberLong": 5}"#).unwrap(),
+            ID::Int64(5)
+        );
+        assert_eq!(
+            parse(legacy, r#"{"$numberLong": 9223372036854775807}"#).unwrap(),
+            ID::Int64(i64::MAX)
+        );
+        assert_eq!(
+            parse(legacy, r#"{"$numberLong": "5"}"#).unwrap(),
+            ID::Int64(5)
+        );
+        assert!(parse(current, r#"{"$numberLong": 5}"#).is_err());
+        assert!(parse(legacy, r#"{"$numberLong": 5.5}"#).is_err());
+        assert!(parse(legacy, r#"{"$numberLong": 5, "x": 1}"#).is_err());
+        assert_eq!(
+            parse(legacy.strict_maps(true), r#"{"$numberLong": 5}"#).unwrap(),
+            ID::Int64(5)
+        );
+    }
+
+    #[test]
+    fn test_object_id_strings() {
+        let oid = bson::oid::ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let inputs = [
+            r#""$oid:5eaefffa00c9fdf000c46fdc""#,
+            r#"" $OID:5EAEFFFA00C9FDF000C46FDC ""#,
+            r#""5eaefffa00c9fdf000c46fdc""#,
+        ];
+        let expected = |mode, input: &str, is_oid: bool| {
+            let options = DeserializeOptions::new().object_id_strings(mode);
+            let expected = if is_oid {
+                ID::ObjectId(oid.clone())
+            } else {
+                ID::String(serde_json::from_str(input).unwrap())
+            };
+            assert_eq!(parse(options, input).unwrap(), expected, "{}", input);
+        };
+        for (input, is_oid) in inputs.iter().zip(&[true, false, false]) {
+            expected(ObjectIdStrings::Canonical, input, *is_oid);
+            expected(ObjectIdStrings::Never, input, false);
+            expected(ObjectIdStrings::Lenient, input, true);
+        }
+        expected(ObjectIdStrings::Lenient, r#""$oid:nothex""#, false);
+        expected(ObjectIdStrings::Lenient, r#""$oi""#, false);
+
+        let never = DeserializeOptions::new().object_id_strings(ObjectIdStrings::Never);
+        assert_eq!(
+            parse(never, r#"{"$oid": "5eaefffa00c9fdf000c46fdc"}"#).unwrap(),
+            ID::ObjectId(oid)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Added and removed IDs between two `IDSet`s
+
+use crate::{IDSet, ID};
+use bson::Document;
+
+/// Which side of a [`diff`] an ID is on
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IdChange {
+    Added,
+    Removed,
+    Unchanged,
+}
+
+/// The IDs added, removed and kept between two sets, from [`diff`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IdDiff {
+    /// IDs only in the new set
+    pub added: IDSet,
+    /// IDs only in the old set
+    pub removed: IDSet,
+    /// IDs in both sets
+    pub unchanged: IDSet,
+}
+
+impl IdDiff {
+    /// The IDs with `change`
+    pub fn get(&self, change: IdChange) -> &IDSet {
+        match change {
+            IdChange::Added => &self.added,
+            IdChange::Removed => &self.removed,
+            IdChange::Unchanged => &self.unchanged,
+        }
+    }
+
+    /// Filter matching documents whose `_id` has `change`, e.g. the removed
+    /// ones to delete
+    pub fn to_in_filter(&self, change: IdChange) -> Document {
+        self.get(change).to_in_filter()
+    }
+
+    /// Whether nothing was added or removed
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Every ID together with its change: added, then removed, then unchanged
+    pub fn iter(&self) -> impl Iterator<Item = (IdChange, &ID)> + '_ {
+        let bucket = move |change| self.get(change).iter().map(move |id| (change, id));
+        bucket(IdChange::Added)
+            .chain(bucket(IdChange::Removed))
+            .chain(bucket(IdChange::Unchanged))
+    }
+
+    /// Only the added and removed IDs, for sync jobs that skip unchanged ones
+    pub fn changes(&self) -> impl Iterator<Item = (IdChange, &ID)> + '_ {
+        self.iter()
+            .filter(|(change, _)| *change != IdChange::Unchanged)
+    }
+}
+
+/// Compare the IDs of `old` and `new`, e.g. a collection's IDs against those
+/// of an external system
+pub fn diff(old: &IDSet, new: &IDSet) -> IdDiff {
+    let mut diff = IdDiff::default();
+    for id in new.iter() {
+        if old.contains(id) {
+            diff.unchanged.insert(id.clone());
+        } else {
+            diff.added.insert(id.clone());
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|id| !new.contains(*id))
+        .cloned()
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+
+    fn set(ids: &[i64]) -> IDSet {
+        ids.iter().map(|i| ID::Int64(*i)).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let diff = diff(&set(&[1, 2, 3]), &set(&[2, 3, 4]));
+        assert_eq!(diff.added, set(&[4]));
+        assert_eq!(diff.removed, set(&[1]));
+        assert_eq!(diff.unchanged, set(&[2, 3]));
+        assert!(!diff.is_unchanged());
+        assert_eq!(
+            diff.to_in_filter(IdChange::Removed),
+            doc! { "_id": { "$in": [1_i64] } }
+        );
+
+        let changes: Vec<_> = diff.changes().collect();
+        assert_eq!(
+            changes,
+            vec![
+                (IdChange::Added, &ID::Int64(4)),
+                (IdChange::Removed, &ID::Int64(1))
+            ]
+        );
+        assert_eq!(diff.iter().count(), 4);
+        assert!(super::diff(&set(&[1]), &set(&[1])).is_unchanged());
+    }
+}
@@ -0,0 +1,138 @@
+//! Human-readable reasons why two IDs don't compare equal
+
+use crate::{pipeline::IdType, ID};
+use std::fmt;
+
+/// Why two IDs differ, from [`ID::explain_mismatch`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MismatchReason {
+    /// The IDs are equal
+    Equal,
+    /// One is a String ID holding the canonical string of the other, an
+    /// ObjectId; see [`ID::normalize`]
+    NotNormalized,
+    /// Same canonical string but different variants, e.g. `42` and `"42"`
+    Variant { left: IdType, right: IdType },
+    /// Equal apart from surrounding whitespace
+    Whitespace,
+    /// Equal apart from letter case
+    Case,
+    /// Equal apart from the `$oid:` prefix, e.g. a bare hex string and the
+    /// ObjectId it spells
+    Prefix,
+    /// Nothing explains the difference
+    Different { left: IdType, right: IdType },
+}
+
+impl fmt::Display for MismatchReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchReason::Equal => f.write_str("the IDs are equal"),
+            MismatchReason::NotNormalized => {
+                f.write_str("a String ID holds the canonical string of the ObjectId")
+            }
+            MismatchReason::Variant { left, right } => write!(
+                f,
+                "the IDs read the same but are {:?} and {:?}",
+                left, right
+            ),
+            MismatchReason::Whitespace => f.write_str("the IDs differ by surrounding whitespace"),
+            MismatchReason::Case => f.write_str("the IDs differ in letter case"),
+            MismatchReason::Prefix => f.write_str("the IDs differ by the $oid: prefix"),
+            MismatchReason::Different { left, right } => {
+                write!(f, "the IDs are different {:?} and {:?} values", left, right)
+            }
+        }
+    }
+}
+
+fn without_prefix(s: &str) -> &str {
+    s.strip_prefix("$oid:").unwrap_or(s)
+}
+
+impl ID {
+    /// Explain why this ID and `other` differ though they may look the same
+    ///
+    /// Compares canonical strings and recognizes one cause at a time, checked
+    /// in the order of [`MismatchReason`]'s variants; IDs differing in more
+    /// than one way are reported as `Different`.
+    pub fn explain_mismatch(&self, other: &ID) -> MismatchReason {
+        if self == other {
+            return MismatchReason::Equal;
+        }
+        if self.eq_normalized(other) {
+            return MismatchReason::NotNormalized;
+        }
+        let (left, right) = (IdType::from(self), IdType::from(other));
+        let (a, b) = (self.to_string(), other.to_string());
+        if a == b {
+            MismatchReason::Variant { left, right }
+        } else if a.trim() == b.trim() {
+            MismatchReason::Whitespace
+        } else if a.to_lowercase() == b.to_lowercase() {
+            MismatchReason::Case
+        } else if without_prefix(&a) == without_prefix(&b) {
+            MismatchReason::Prefix
+        } else {
+            MismatchReason::Different { left, right }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_explain_mismatch() {
+        let hex = "5eaefffa00c9fdf000c46fdc";
+        let oid = ID::ObjectId(ObjectId::with_string(hex).unwrap());
+        let s = |v: &str| ID::with_string(v);
+        let cases = vec![
+            (oid.clone(), oid.clone(), MismatchReason::Equal),
+            (
+                oid.clone(),
+                ID::String(format!("$oid:{}", hex)),
+                MismatchReason::NotNormalized,
+            ),
+            (
+                ID::Int64(42),
+                s("42"),
+                MismatchReason::Variant {
+                    left: IdType::Int64,
+                    right: IdType::String,
+                },
+            ),
+            (s("abc "), s("abc"), MismatchReason::Whitespace),
+            (s("\tabc"), s("abc\n"), MismatchReason::Whitespace),
+            (s("Ünï"), s("üNÏ"), MismatchReason::Case),
+            (oid.clone(), s(hex), MismatchReason::Prefix),
+            (s("$oid:x"), s("x"), MismatchReason::Prefix),
+            (
+                oid.clone(),
+                s(&hex.to_uppercase()),
+                MismatchReason::Different {
+                    left: IdType::ObjectId,
+                    right: IdType::String,
+                },
+            ),
+            (
+                s("a"),
+                s("b"),
+                MismatchReason::Different {
+                    left: IdType::String,
+                    right: IdType::String,
+                },
+            ),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(a.explain_mismatch(&b), expected, "{:?} vs {:?}", a, b);
+            assert!(!b.explain_mismatch(&a).to_string().is_empty());
+        }
+        assert_eq!(
+            ID::Int64(42).explain_mismatch(&s("42")).to_string(),
+            "the IDs read the same but are Int64 and String"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! C interface for validating and formatting IDs
+//!
+//! `include/mongodb_id.h` declares these types and functions for C and C++;
+//! the `mongodb_id_ffi` crate builds them into static and shared libraries.
+use crate::{ParseIdError, ID};
+use bson::oid::ObjectId;
+use std::{ptr, slice, str};
+
+/// The variant held by a [`CId`], stored in its `tag` as a `u32`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CIdTag {
+    ObjectId = 0,
+    String = 1,
+    Int64 = 2,
+}
+
+impl CIdTag {
+    /// The tag with the value `tag`, if any
+    pub fn from_u32(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(CIdTag::ObjectId),
+            1 => Some(CIdTag::String),
+            2 => Some(CIdTag::Int64),
+            _ => None,
+        }
+    }
+}
+
+/// UTF-8 bytes of a string ID, not NUL terminated
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct CIdStr {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union CIdValue {
+    pub object_id: [u8; 12],
+    pub string: CIdStr,
+    pub int64: i64,
+}
+
+/// An ID laid out for C: `tag` says which field of `value` is set
+///
+/// `tag` is a plain `u32` holding a [`CIdTag`] value, since C code can store
+/// any number in it; other values are rejected with [`CIdStatus::InvalidTag`].
+/// A string payload from [`mongodb_id_parse`] is owned by Rust and must be
+/// released with [`mongodb_id_free`]. A `CId` built on the C side keeps
+/// ownership of its string and must not be passed to `mongodb_id_free`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CId {
+    pub tag: u32,
+    pub value: CIdValue,
+}
+
+/// Result of the fallible functions
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CIdStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    Empty = 3,
+    Whitespace = 4,
+    InvalidObjectId = 5,
+    InvalidTag = 6,
+}
+
+impl From<ParseIdError> for CIdStatus {
+    fn from(e: ParseIdError) -> Self {
+        match e {
+            ParseIdError::Empty => CIdStatus::Empty,
+            ParseIdError::Whitespace => CIdStatus::Whitespace,
+            ParseIdError::InvalidObjectId => CIdStatus::InvalidObjectId,
+        }
+    }
+}
+
+impl From<ID> for CId {
+    fn from(id: ID) -> Self {
+        match id {
+            ID::ObjectId(o) => CId {
+                tag: CIdTag::ObjectId as u32,
+                value: CIdValue {
+                    object_id: o.bytes(),
+                },
+            },
+            ID::String(s) => {
+                let len = s.len();
+                let bytes = Box::into_raw(s.into_bytes().into_boxed_slice());
+                CId {
+                    tag: CIdTag::String as u32,
+                    value: CIdValue {
+                        string: CIdStr {
+                            ptr: bytes as *mut u8,
+                            len,
+                        },
+                    },
+                }
+            }
+            ID::Int64(i) => CId {
+                tag: CIdTag::Int64 as u32,
+                value: CIdValue { int64: i },
+            },
+        }
+    }
+}
+
+/// Borrow the ID `id` describes, without taking ownership of its string
+///
+/// # Safety
+///
+/// For string IDs `ptr` must be valid for reads of `len` bytes or be null with
+/// `len` 0.
+unsafe fn with_borrowed<T>(id: &CId, f: impl FnOnce(&ID) -> T) -> Result<T, CIdStatus> {
+    match CIdTag::from_u32(id.tag).ok_or(CIdStatus::InvalidTag)? {
+        CIdTag::ObjectId => Ok(f(&ID::ObjectId(ObjectId::with_bytes(id.value.object_id)))),
+        CIdTag::Int64 => Ok(f(&ID::Int64(id.value.int64))),
+        CIdTag::String => {
+            let CIdStr { ptr, len } = id.value.string;
+            let bytes = if len == 0 {
+                &[][..]
+            } else if ptr.is_null() {
+                return Err(CIdStatus::NullPointer);
+            } else {
+                slice::from_raw_parts(ptr, len)
+            };
+            let s = str::from_utf8(bytes).map_err(|_| CIdStatus::InvalidUtf8)?;
+            // a temporary owned copy keeps the C side's buffer untouched
+            Ok(f(&ID::String(s.to_string())))
+        }
+    }
+}
+
+/// Parse `len` bytes at `input` strictly, as `ID::parse_strict` does
+///
+/// # Safety
+///
+/// `input` must be valid for reads of `len` bytes and `out` valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn mongodb_id_parse(
+    input: *const u8,
+    len: usize,
+    out: *mut CId,
+) -> CIdStatus {
+    if (input.is_null() && len != 0) || out.is_null() {
+        return CIdStatus::NullPointer;
+    }
+    let bytes = if len == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(input, len)
+    };
+    let s = match str::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(_) => return CIdStatus::InvalidUtf8,
+    };
+    match ID::parse_strict(s) {
+        Ok(id) => {
+            ptr::write(out, CId::from(id));
+            CIdStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Write the canonical string of `id` into `buf`, like `snprintf`
+///
+/// Returns the length of the canonical string, not counting the NUL. The
+/// output (NUL terminated) is only written if that is less than `buf_len`,
+/// so call with a null `buf` and `buf_len` 0 to size the buffer. Returns
+/// `SIZE_MAX` if `id` is null, has an invalid tag or holds invalid UTF-8.
+///
+/// # Safety
+///
+/// `id` must point to a valid `CId` and `buf` be valid for writes of `buf_len`
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mongodb_id_format(id: *const CId, buf: *mut u8, buf_len: usize) -> usize {
+    if id.is_null() {
+        return usize::MAX;
+    }
+    let canonical = match with_borrowed(&*id, ID::to_string) {
+        Ok(canonical) => canonical,
+        Err(_) => return usize::MAX,
+    };
+    if !buf.is_null() && canonical.len() < buf_len {
+        ptr::copy_nonoverlapping(canonical.as_ptr(), buf, canonical.len());
+        *buf.add(canonical.len()) = 0;
+    }
+    canonical.len()
+}
+
+/// Release the string owned by a `CId` from [`mongodb_id_parse`]
+///
+/// Leaves `id` as an empty string ID, so freeing twice is harmless.
+///
+/// # Safety
+///
+/// `id` must be null or point to a `CId` filled in by `mongodb_id_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn mongodb_id_free(id: *mut CId) {
+    if id.is_null() || (*id).tag != CIdTag::String as u32 {
+        return;
+    }
+    let CIdStr { ptr, len } = (*id).value.string;
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+    (*id).value.string = CIdStr {
+        ptr: ptr::null_mut(),
+        len: 0,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+
+    fn parse(input: &str) -> Result<CId, CIdStatus> {
+        let mut out = MaybeUninit::uninit();
+        match unsafe { mongodb_id_parse(input.as_ptr(), input.len(), out.as_mut_ptr()) } {
+            CIdStatus::Ok => Ok(unsafe { out.assume_init() }),
+            status => Err(status),
+        }
+    }
+
+    fn format(id: &CId) -> String {
+        let len = unsafe { mongodb_id_format(id, ptr::null_mut(), 0) };
+        let mut buf = vec![0xff; len + 1];
+        assert_eq!(
+            unsafe { mongodb_id_format(id, buf.as_mut_ptr(), buf.len()) },
+            len
+        );
+        assert_eq!(buf.pop(), Some(0));
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let mut id = parse("$oid:5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(id.tag, CIdTag::ObjectId as u32);
+        assert_eq!(format(&id), "$oid:5eaefffa00c9fdf000c46fdc");
+        unsafe { mongodb_id_free(&mut id) };
+
+        let mut id = parse("users/1").unwrap();
+        assert_eq!(id.tag, CIdTag::String as u32);
+        assert_eq!(format(&id), "users/1");
+        unsafe {
+            mongodb_id_free(&mut id);
+            mongodb_id_free(&mut id);
+        }
+        assert_eq!(format(&id), "");
+
+        let id = CId {
+            tag: CIdTag::Int64 as u32,
+            value: CIdValue { int64: -3 },
+        };
+        assert_eq!(format(&id), "-3");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("").err(), Some(CIdStatus::Empty));
+        assert_eq!(parse(" a").err(), Some(CIdStatus::Whitespace));
+        assert_eq!(parse("$oid:zz").err(), Some(CIdStatus::InvalidObjectId));
+        let invalid = [0xff_u8];
+        let mut out = MaybeUninit::uninit();
+        assert_eq!(
+            unsafe { mongodb_id_parse(invalid.as_ptr(), 1, out.as_mut_ptr()) },
+            CIdStatus::InvalidUtf8
+        );
+        assert_eq!(
+            unsafe { mongodb_id_parse(ptr::null(), 1, out.as_mut_ptr()) },
+            CIdStatus::NullPointer
+        );
+
+        let mut buf = [0xff_u8; 4];
+        let id = CId {
+            tag: CIdTag::Int64 as u32,
+            value: CIdValue { int64: 12345 },
+        };
+        assert_eq!(
+            unsafe { mongodb_id_format(&id, buf.as_mut_ptr(), buf.len()) },
+            5
+        );
+        assert_eq!(buf, [0xff; 4]);
+    }
+
+    #[test]
+    fn test_invalid_tag() {
+        let mut id = CId {
+            tag: 7,
+            value: CIdValue { int64: 1 },
+        };
+        assert_eq!(
+            unsafe { with_borrowed(&id, ID::to_string) }.err(),
+            Some(CIdStatus::InvalidTag)
+        );
+        assert_eq!(
+            unsafe { mongodb_id_format(&id, ptr::null_mut(), 0) },
+            usize::MAX
+        );
+        unsafe { mongodb_id_free(&mut id) };
+        assert_eq!(CIdTag::from_u32(2), Some(CIdTag::Int64));
+        assert_eq!(CIdTag::from_u32(3), None);
+    }
+}
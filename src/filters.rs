@@ -0,0 +1,76 @@
+//! Query filters matching several `_id`s
+
+use crate::ID;
+use bson::{doc, Bson, Document};
+
+/// Filter matching documents whose `_id` is any of `ids`
+///
+/// Produces `{"_id": {"$in": [...]}}`.
+pub fn any_of(ids: &[ID]) -> Document {
+    doc! { "_id": { "$in": ID::many_to_bson(ids) } }
+}
+
+/// `{"_id": {"$in": [...]}}` over already converted values
+pub(crate) fn in_filter<I: IntoIterator<Item = Bson>>(ids: I) -> Document {
+    let ids: Vec<Bson> = ids.into_iter().collect();
+    doc! { "_id": { "$in": ids } }
+}
+
+/// Split a large `$in` filter into one [`any_of`] filter per `chunk_size` IDs
+///
+/// A single `$in` over a very large set of IDs can exceed the maximum BSON
+/// document size, so query each chunk separately instead.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0.
+pub fn chunked_in(ids: &[ID], chunk_size: usize) -> impl Iterator<Item = Document> + '_ {
+    ids.chunks(chunk_size).map(any_of)
+}
+
+/// Filter matching every document except the one with the given `_id`
+///
+/// Produces `{"_id": {"$ne": <id>}}`.
+pub fn not(id: &ID) -> Document {
+    doc! { "_id": { "$ne": id.to_bson() } }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_filter_by_id() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(ID::with_oid(oid.clone()).filter(), doc! { "_id": oid });
+        assert_eq!(ID::with_i64(7).filter(), doc! { "_id": 7_i64 });
+    }
+
+    #[test]
+    fn test_any_of() {
+        let ids = vec![ID::with_string("a"), ID::with_i64(2)];
+        assert_eq!(any_of(&ids), doc! { "_id": { "$in": ["a", 2_i64] } });
+        assert_eq!(any_of(&[]), doc! { "_id": { "$in": [] } });
+    }
+
+    #[test]
+    fn test_chunked_in() {
+        let ids: Vec<ID> = (0..5).map(ID::with_i64).collect();
+        let chunks: Vec<Document> = chunked_in(&ids, 2).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                doc! { "_id": { "$in": [0_i64, 1_i64] } },
+                doc! { "_id": { "$in": [2_i64, 3_i64] } },
+                doc! { "_id": { "$in": [4_i64] } },
+            ]
+        );
+        assert_eq!(chunked_in(&[], 2).count(), 0);
+    }
+
+    #[test]
+    fn test_not() {
+        assert_eq!(not(&ID::with_string("a")), doc! { "_id": { "$ne": "a" } });
+    }
+}
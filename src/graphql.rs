@@ -0,0 +1,216 @@
+//! Helpers for exposing `ID` through GraphQL schemas
+use crate::ID;
+use bson::{doc, Bson, Document};
+use serde::Deserialize;
+
+/// Description of the formats an `ID` accepts, used by default in SDL
+pub const DEFAULT_DESCRIPTION: &str = "An identifier, sent as a string or integer. \
+ObjectIds are written as `$oid:` followed by 24 hex digits, anything else is a plain string ID.";
+
+/// SDL for the custom scalar an `ID` is exposed as
+///
+/// The built-in GraphQL `ID` can't carry `@specifiedBy`, so this describes a
+/// custom scalar, named `MongoID` unless configured otherwise.
+///
+/// ```
+/// use mongodb_id::graphql::ScalarDefinition;
+///
+/// let sdl = ScalarDefinition::new()
+///     .description("Entity identifier")
+///     .specified_by("https://example.com/ids")
+///     .to_sdl();
+/// assert_eq!(
+///     sdl,
+///     "\"\"\"\nEntity identifier\n\"\"\"\nscalar MongoID @specifiedBy(url: \"https://example.com/ids\")\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScalarDefinition {
+    name: String,
+    description: Option<String>,
+    specified_by: Option<String>,
+}
+
+impl Default for ScalarDefinition {
+    fn default() -> Self {
+        ScalarDefinition {
+            name: "MongoID".to_string(),
+            description: Some(DEFAULT_DESCRIPTION.to_string()),
+            specified_by: None,
+        }
+    }
+}
+
+impl ScalarDefinition {
+    pub fn new() -> Self {
+        ScalarDefinition::default()
+    }
+
+    /// Name of the scalar, defaults to `MongoID`
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Description of the scalar, defaults to [`DEFAULT_DESCRIPTION`]
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn without_description(mut self) -> Self {
+        self.description = None;
+        self
+    }
+
+    /// URL of the document specifying the scalar, emitted as `@specifiedBy`
+    pub fn specified_by<S: Into<String>>(mut self, url: S) -> Self {
+        self.specified_by = Some(url.into());
+        self
+    }
+
+    /// Render the scalar definition as SDL
+    pub fn to_sdl(&self) -> String {
+        let mut sdl = String::new();
+        if let Some(description) = &self.description {
+            sdl.push_str("\"\"\"\n");
+            sdl.push_str(&description.replace("\"\"\"", "\\\"\"\""));
+            sdl.push_str("\n\"\"\"\n");
+        }
+        sdl.push_str("scalar ");
+        sdl.push_str(&self.name);
+        if let Some(url) = &self.specified_by {
+            sdl.push_str(" @specifiedBy(url: \"");
+            for c in url.chars() {
+                match c {
+                    '"' => sdl.push_str("\\\""),
+                    '\\' => sdl.push_str("\\\\"),
+                    c => sdl.push(c),
+                }
+            }
+            sdl.push_str("\")");
+        }
+        sdl.push('\n');
+        sdl
+    }
+}
+
+/// The filter input type commonly accepted for ID fields
+///
+/// Deserializes from a GraphQL input object like
+/// `{eq: "$oid:5eaefffa00c9fdf000c46fdc", nin: [1, 2], exists: true}`, each
+/// value read as an `ID` would be.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdFilterInput {
+    pub eq: Option<ID>,
+    pub ne: Option<ID>,
+    #[serde(rename = "in")]
+    pub in_: Option<Vec<ID>>,
+    pub nin: Option<Vec<ID>>,
+    pub exists: Option<bool>,
+}
+
+/// Translate `arg` into a filter on `_id`
+///
+/// See [`resolve_field_filter`].
+pub fn resolve_filter(arg: IdFilterInput) -> Document {
+    resolve_field_filter("_id", arg)
+}
+
+/// Translate `arg` into a filter on `field`
+///
+/// Each ID becomes its BSON type, so `"$oid:..."` strings query ObjectIds and
+/// integers query Int64s. The operators given are combined, producing e.g.
+/// `{<field>: {"$in": [...], "$ne": <id>}}`, and an empty input matches every
+/// document.
+pub fn resolve_field_filter(field: &str, arg: IdFilterInput) -> Document {
+    let mut ops = Document::new();
+    if let Some(id) = arg.eq {
+        ops.insert("$eq", id.into_bson());
+    }
+    if let Some(id) = arg.ne {
+        ops.insert("$ne", id.into_bson());
+    }
+    if let Some(ids) = arg.in_ {
+        ops.insert(
+            "$in",
+            ids.into_iter().map(ID::into_bson).collect::<Vec<Bson>>(),
+        );
+    }
+    if let Some(ids) = arg.nin {
+        ops.insert(
+            "$nin",
+            ids.into_iter().map(ID::into_bson).collect::<Vec<Bson>>(),
+        );
+    }
+    if let Some(exists) = arg.exists {
+        ops.insert("$exists", exists);
+    }
+    if ops.is_empty() {
+        Document::new()
+    } else {
+        doc! { field: ops }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_resolve_filter() {
+        let input: IdFilterInput = serde_json::from_str(
+            r#"{"eq": "$oid:5eaefffa00c9fdf000c46fdc", "in": [1, "a"], "nin": [], "exists": true}"#,
+        )
+        .unwrap();
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(
+            resolve_filter(input),
+            doc! {
+                "_id": {
+                    "$eq": oid,
+                    "$in": [1_i64, "a"],
+                    "$nin": [],
+                    "$exists": true,
+                }
+            }
+        );
+        let input = IdFilterInput {
+            ne: Some(ID::Int64(3)),
+            ..IdFilterInput::default()
+        };
+        assert_eq!(
+            resolve_field_filter("owner", input),
+            doc! { "owner": { "$ne": 3_i64 } }
+        );
+        assert_eq!(resolve_filter(IdFilterInput::default()), doc! {});
+        assert!(serde_json::from_str::<IdFilterInput>(r#"{"gt": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_default_sdl() {
+        assert_eq!(
+            ScalarDefinition::new().to_sdl(),
+            format!("\"\"\"\n{}\n\"\"\"\nscalar MongoID\n", DEFAULT_DESCRIPTION)
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        let sdl = ScalarDefinition::new()
+            .name("Id")
+            .description("quotes \"\"\" inside")
+            .specified_by("https://example.com/\"x\"")
+            .to_sdl();
+        assert_eq!(
+            sdl,
+            "\"\"\"\nquotes \\\"\"\" inside\n\"\"\"\nscalar Id @specifiedBy(url: \"https://example.com/\\\"x\\\"\")\n"
+        );
+        assert_eq!(
+            ScalarDefinition::new().without_description().to_sdl(),
+            "scalar MongoID\n"
+        );
+    }
+}
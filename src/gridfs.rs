@@ -0,0 +1,57 @@
+//! GridFS `files_id` lookups for chunk collections
+
+use crate::ID;
+use bson::{doc, Bson, Document};
+use std::convert::TryFrom;
+
+impl ID {
+    /// Convert the `_id` of a GridFS files collection entry
+    ///
+    /// GridFS file IDs are ObjectIds by default but drivers accept any BSON value,
+    /// so this returns `None` for types `ID` can't represent instead of panicking.
+    pub fn from_gridfs(value: &Bson) -> Option<Self> {
+        ID::try_from(value).ok()
+    }
+}
+
+/// Filter matching the chunks of the file with the given ID: `{"files_id": <id>}`
+pub fn chunks_filter(file_id: &ID) -> Document {
+    doc! { "files_id": file_id.to_bson() }
+}
+
+/// Filter matching the chunks of any of the given files: `{"files_id": {"$in": [...]}}`
+pub fn chunks_filter_any(file_ids: &[ID]) -> Document {
+    doc! { "files_id": { "$in": ID::many_to_bson(file_ids) } }
+}
+
+/// Sort to read a file's chunks back in order: `{"n": 1}`
+pub fn chunks_sort() -> Document {
+    doc! { "n": 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_from_gridfs() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(
+            ID::from_gridfs(&Bson::ObjectId(oid.clone())),
+            Some(ID::ObjectId(oid))
+        );
+        assert_eq!(ID::from_gridfs(&Bson::Int32(3)), Some(ID::Int64(3)));
+        assert_eq!(ID::from_gridfs(&Bson::Boolean(true)), None);
+    }
+
+    #[test]
+    fn test_chunks_filters() {
+        let id = ID::with_string("report.pdf");
+        assert_eq!(chunks_filter(&id), doc! { "files_id": "report.pdf" });
+        assert_eq!(
+            chunks_filter_any(&[id, ID::with_i64(1)]),
+            doc! { "files_id": { "$in": ["report.pdf", 1_i64] } }
+        );
+    }
+}
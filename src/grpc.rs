@@ -0,0 +1,163 @@
+//! gRPC metadata and status helpers, independent of any gRPC library
+//!
+//! ASCII metadata values are the typed string form of [`map_keys`], so
+//! Int64 IDs stay distinct from String IDs, with `%` and every byte outside
+//! printable ASCII, including spaces, percent-escaped. Keys ending in `-bin`
+//! carry the compact bytes of [`ID::to_bytes`] instead.
+//!
+//! ```
+//! use mongodb_id::{grpc, ID};
+//!
+//! let id = ID::with_string("Zoë 1");
+//! assert_eq!(grpc::to_ascii_value(&id), "Zo%C3%AB%201");
+//! assert_eq!(grpc::from_ascii_value("Zo%C3%AB%201"), Ok(id));
+//! assert_eq!(grpc::to_ascii_value(&ID::Int64(7)), "$int:7");
+//! ```
+//!
+//! [`map_keys`]: crate::map_keys
+use crate::{
+    map_keys::{parse_key, TypedKey},
+    DecodeIdError, ID,
+};
+use std::{error, fmt, fmt::Write};
+
+/// gRPC status code `INVALID_ARGUMENT`, for requests carrying a malformed ID
+pub const INVALID_ARGUMENT: i32 = 3;
+
+/// Error returned by [`from_ascii_value`] and [`from_binary_value`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetadataError {
+    /// The value has bytes outside printable ASCII, a malformed escape, or
+    /// escapes that don't decode to UTF-8
+    InvalidEncoding,
+    /// The value of a `-bin` key isn't a valid compact ID
+    Binary(DecodeIdError),
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::InvalidEncoding => f.write_str("ID metadata value is not escaped ASCII"),
+            MetadataError::Binary(e) => write!(f, "invalid binary ID metadata: {}", e),
+        }
+    }
+}
+
+impl error::Error for MetadataError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MetadataError::Binary(e) => Some(e),
+            MetadataError::InvalidEncoding => None,
+        }
+    }
+}
+
+/// The value of an ASCII metadata key holding `id`
+pub fn to_ascii_value(id: &ID) -> String {
+    let typed = TypedKey(id).to_string();
+    let mut value = String::with_capacity(typed.len());
+    for b in typed.bytes() {
+        if b.is_ascii_graphic() && b != b'%' {
+            value.push(char::from(b));
+        } else {
+            write!(value, "%{:02X}", b).unwrap();
+        }
+    }
+    value
+}
+
+/// Read an ID written by [`to_ascii_value`]
+///
+/// As with map keys, values with an unknown `$` prefix or an invalid payload
+/// are taken as String IDs.
+pub fn from_ascii_value(value: &str) -> Result<ID, MetadataError> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.bytes();
+    while let Some(b) = rest.next() {
+        if !b.is_ascii_graphic() {
+            return Err(MetadataError::InvalidEncoding);
+        }
+        if b != b'%' {
+            bytes.push(b);
+            continue;
+        }
+        let mut digit = || {
+            rest.next()
+                .and_then(|d| char::from(d).to_digit(16))
+                .ok_or(MetadataError::InvalidEncoding)
+        };
+        let high = digit()?;
+        let low = digit()?;
+        bytes.push((high << 4 | low) as u8);
+    }
+    let typed = String::from_utf8(bytes).map_err(|_| MetadataError::InvalidEncoding)?;
+    Ok(parse_key(typed))
+}
+
+/// The value of a `-bin` metadata key holding `id`
+pub fn to_binary_value(id: &ID) -> Vec<u8> {
+    id.to_bytes()
+}
+
+/// Read an ID written by [`to_binary_value`]
+pub fn from_binary_value(bytes: &[u8]) -> Result<ID, MetadataError> {
+    ID::from_bytes(bytes).map_err(MetadataError::Binary)
+}
+
+/// Code and message of the status to answer a malformed ID in `field` with,
+/// e.g. as `tonic::Status::new(code.into(), message)`
+pub fn invalid_argument(field: &str, error: &dyn error::Error) -> (i32, String) {
+    (
+        INVALID_ARGUMENT,
+        format!("invalid ID in {}: {}", field, error),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseIdError;
+
+    #[test]
+    fn test_ascii_value() {
+        let ids = [
+            ID::from_string("$oid:5eaefffa00c9fdf000c46fdc"),
+            ID::Int64(-7),
+            ID::with_string("7"),
+            ID::with_string("$int:7"),
+            ID::with_string(" 100% ü\n"),
+        ];
+        for id in &ids {
+            let value = to_ascii_value(id);
+            assert!(value.bytes().all(|b| b.is_ascii_graphic()), "{}", value);
+            assert_eq!(from_ascii_value(&value).as_ref(), Ok(id));
+        }
+        assert_eq!(to_ascii_value(&ids[4]), "%20100%25%20%C3%BC%0A");
+        assert_eq!(from_ascii_value("a%2f"), Ok(ID::with_string("a/")));
+        for value in ["a b", "%", "%4", "%zz", "%FF", "ü"] {
+            assert_eq!(
+                from_ascii_value(value),
+                Err(MetadataError::InvalidEncoding),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_binary_value() {
+        let id = ID::Int64(7);
+        assert_eq!(from_binary_value(&to_binary_value(&id)), Ok(id));
+        assert_eq!(
+            from_binary_value(&[]),
+            Err(MetadataError::Binary(DecodeIdError::Empty))
+        );
+    }
+
+    #[test]
+    fn test_invalid_argument() {
+        let (code, message) = invalid_argument("user_id", &ParseIdError::Empty);
+        assert_eq!(code, INVALID_ARGUMENT);
+        assert_eq!(message, "invalid ID in user_id: ID is empty");
+    }
+}
@@ -0,0 +1,212 @@
+//! IDs carrying a precomputed hash for fast map lookups
+
+use crate::ID;
+use fnv::FnvHasher;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hash, Hasher},
+    ops::Deref,
+};
+
+/// An ID carrying its precomputed hash
+///
+/// Hashing a `HashedId` only feeds the stored 64-bit hash, so with
+/// [`PrecomputedHasher`] a map probe costs no hashing at all. Build a
+/// `HashedId` once for keys that are looked up repeatedly.
+#[derive(Clone, Debug)]
+pub struct HashedId {
+    hash: u64,
+    id: ID,
+}
+
+impl HashedId {
+    pub fn new(id: ID) -> Self {
+        HashedId {
+            hash: HashedId::hash_of(&id),
+            id,
+        }
+    }
+
+    /// The hash a `HashedId` of `id` would carry, without cloning `id`
+    pub fn hash_of(id: &ID) -> u64 {
+        let mut hasher = FnvHasher::default();
+        id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The precomputed hash
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn id(&self) -> &ID {
+        &self.id
+    }
+
+    pub fn into_inner(self) -> ID {
+        self.id
+    }
+}
+
+impl From<ID> for HashedId {
+    fn from(id: ID) -> Self {
+        HashedId::new(id)
+    }
+}
+
+impl Deref for HashedId {
+    type Target = ID;
+
+    fn deref(&self) -> &ID {
+        &self.id
+    }
+}
+
+impl PartialEq for HashedId {
+    fn eq(&self, other: &Self) -> bool {
+        // equal IDs always have equal hashes, so this only short-circuits misses
+        self.hash == other.hash && self.id == other.id
+    }
+}
+
+impl Eq for HashedId {}
+
+impl Hash for HashedId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// A hasher passing through the hash a [`HashedId`] already computed
+///
+/// Only meant for `HashedId` keys - other keys written to it are folded with FNV.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrecomputedHasher(u64);
+
+impl Hasher for PrecomputedHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut hasher = FnvHasher::with_key(self.0);
+        hasher.write(bytes);
+        self.0 = hasher.finish();
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+}
+
+/// A map keyed by precomputed-hash IDs
+pub type HashedIdMap<V> = HashMap<HashedId, V, BuildHasherDefault<PrecomputedHasher>>;
+
+/// A hashbrown [`HashedIdMap`], whose raw-entry API allows the lookups of
+/// [`HashedIdMapExt`]
+#[cfg(feature = "hashbrown")]
+pub type RawHashedIdMap<V> = hashbrown::HashMap<HashedId, V, BuildHasherDefault<PrecomputedHasher>>;
+
+/// Lookups in a [`RawHashedIdMap`] by plain `&ID`, without building a
+/// `HashedId` key
+#[cfg(feature = "hashbrown")]
+pub trait HashedIdMapExt<V> {
+    /// The value for `id`, hashing it once
+    fn get_by_id(&self, id: &ID) -> Option<&V>;
+
+    /// The value for `id`, whose [`HashedId::hash_of`] is `hash`
+    fn get_by_hash(&self, hash: u64, id: &ID) -> Option<&V>;
+
+    /// Insert `value` for `id`, returning the previous value
+    ///
+    /// `id` is only cloned when it isn't in the map yet.
+    fn insert_by_id(&mut self, id: &ID, value: V) -> Option<V>;
+}
+
+#[cfg(feature = "hashbrown")]
+impl<V> HashedIdMapExt<V> for RawHashedIdMap<V> {
+    fn get_by_id(&self, id: &ID) -> Option<&V> {
+        self.get_by_hash(HashedId::hash_of(id), id)
+    }
+
+    fn get_by_hash(&self, hash: u64, id: &ID) -> Option<&V> {
+        self.raw_entry()
+            .from_hash(hash, |key| key.id == *id)
+            .map(|(_, value)| value)
+    }
+
+    fn insert_by_id(&mut self, id: &ID, value: V) -> Option<V> {
+        use hashbrown::hash_map::RawEntryMut;
+
+        let hash = HashedId::hash_of(id);
+        match self.raw_entry_mut().from_hash(hash, |key| key.id == *id) {
+            RawEntryMut::Occupied(mut entry) => Some(entry.insert(value)),
+            RawEntryMut::Vacant(entry) => {
+                let key = HashedId {
+                    hash,
+                    id: id.clone(),
+                };
+                entry.insert_hashed_nocheck(hash, key, value);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashed_id_eq() {
+        assert_eq!(
+            HashedId::new(ID::with_string("a")),
+            HashedId::from(ID::with_string("a"))
+        );
+        assert_ne!(
+            HashedId::new(ID::with_string("a")),
+            HashedId::new(ID::with_i64(1))
+        );
+    }
+
+    #[test]
+    fn test_hashed_id_map() {
+        let key = HashedId::new(ID::with_string("user-1"));
+        let mut map = HashedIdMap::default();
+        map.insert(key.clone(), 1);
+        map.insert(HashedId::new(ID::with_i64(2)), 2);
+        assert_eq!(map.get(&key), Some(&1));
+        assert_eq!(map.get(&HashedId::new(ID::with_string("user-2"))), None);
+        assert_eq!(*key, ID::with_string("user-1"));
+    }
+
+    #[test]
+    fn test_precomputed_hasher_passes_hash_through() {
+        let key = HashedId::new(ID::with_i64(7));
+        let mut hasher = PrecomputedHasher::default();
+        key.hash(&mut hasher);
+        assert_eq!(hasher.finish(), key.hash_value());
+        assert_eq!(HashedId::hash_of(&ID::with_i64(7)), key.hash_value());
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn test_raw_entry_lookups() {
+        let id = ID::with_string("user-1");
+        let mut map = RawHashedIdMap::default();
+        assert_eq!(map.insert_by_id(&id, 1), None);
+        assert_eq!(map.insert_by_id(&id, 2), Some(1));
+        map.insert(HashedId::new(ID::with_i64(2)), 3);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get_by_id(&id), Some(&2));
+        assert_eq!(map.get(&HashedId::new(id.clone())), Some(&2));
+        assert_eq!(map.get_by_hash(HashedId::hash_of(&id), &id), Some(&2));
+        assert_eq!(map.get_by_id(&ID::with_i64(2)), Some(&3));
+        assert_eq!(map.get_by_id(&ID::with_string("user-2")), None);
+        // a matching hash with a different ID is a miss
+        assert_eq!(
+            map.get_by_hash(HashedId::hash_of(&id), &ID::with_string("x")),
+            None
+        );
+    }
+}
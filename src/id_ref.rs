@@ -0,0 +1,234 @@
+//! A borrowed view of an `ID`
+
+use crate::{oid_hex, ID};
+use bson::oid::ObjectId;
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{borrow::Cow, fmt};
+
+/// A view of an ID borrowing its string from the deserialized input
+///
+/// Deserializes the same forms `ID` produces when serialized, but only
+/// allocates for strings that can't be borrowed (e.g. JSON strings containing
+/// escapes). Convert the entries worth keeping with [`IdRef::into_owned`].
+#[derive(Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(not(feature = "redact-debug"), derive(Debug))]
+pub enum IdRef<'a> {
+    ObjectId(ObjectId),
+    String(Cow<'a, str>),
+    Int64(i64),
+}
+
+impl<'a> IdRef<'a> {
+    /// Parse a canonical string the way [`ID::from_string`] does, borrowing it
+    pub fn from_str_ref(s: &'a str) -> Self {
+        IdRef::from_cow(Cow::Borrowed(s))
+    }
+
+    fn from_cow(s: Cow<'a, str>) -> Self {
+        match s.strip_prefix("$oid:").and_then(oid_hex::parse_oid) {
+            Some(oid) => IdRef::ObjectId(oid),
+            None => IdRef::String(s),
+        }
+    }
+
+    pub fn into_owned(self) -> ID {
+        match self {
+            IdRef::ObjectId(o) => ID::ObjectId(o),
+            IdRef::String(s) => ID::String(s.into_owned()),
+            IdRef::Int64(i) => ID::Int64(i),
+        }
+    }
+}
+
+impl<'a> From<&'a ID> for IdRef<'a> {
+    fn from(id: &'a ID) -> Self {
+        match id {
+            ID::ObjectId(o) => IdRef::ObjectId(o.clone()),
+            ID::String(s) => IdRef::String(Cow::Borrowed(s)),
+            ID::Int64(i) => IdRef::Int64(*i),
+        }
+    }
+}
+
+impl From<IdRef<'_>> for ID {
+    fn from(id: IdRef<'_>) -> ID {
+        id.into_owned()
+    }
+}
+
+impl PartialEq<ID> for IdRef<'_> {
+    fn eq(&self, other: &ID) -> bool {
+        match (self, other) {
+            (IdRef::ObjectId(a), ID::ObjectId(b)) => a == b,
+            (IdRef::String(a), ID::String(b)) => a == b,
+            (IdRef::Int64(a), ID::Int64(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for IdRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdRef::ObjectId(o) => f.debug_tuple("ObjectId").field(o).finish(),
+            IdRef::String(s) => f
+                .debug_tuple("String")
+                .field(&crate::redact::RedactedStr(s))
+                .finish(),
+            IdRef::Int64(i) => f.debug_tuple("Int64").field(i).finish(),
+        }
+    }
+}
+
+/// A string borrowed from the input when the deserializer allows it
+struct CowStr<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for CowStr<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CowStrVisitor;
+        impl<'de> Visitor<'de> for CowStrVisitor {
+            type Value = CowStr<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(CowStr(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(CowStr(Cow::Owned(v.to_string())))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+                Ok(CowStr(Cow::Owned(v)))
+            }
+        }
+        deserializer.deserialize_str(CowStrVisitor)
+    }
+}
+
+struct IdRefVisitor;
+impl<'de> Visitor<'de> for IdRefVisitor {
+    type Value = IdRef<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an ID string, integer or {\"$oid\": ...} map")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let key = match access.next_key::<CowStr<'de>>()? {
+            Some(key) => key.0,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let value = access.next_value::<CowStr<'de>>()?.0;
+        let id = match &*key {
+            "$oid" => oid_hex::parse_oid(&value)
+                .map(IdRef::ObjectId)
+                .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(&value), &self))?,
+            "$numberLong" => value
+                .parse()
+                .map(IdRef::Int64)
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&value), &self))?,
+            other => return Err(de::Error::unknown_field(other, &["$oid", "$numberLong"])),
+        };
+        if access.next_key::<CowStr<'de>>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok(id)
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(IdRef::from_str_ref(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(IdRef::from_cow(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(IdRef::from_cow(Cow::Owned(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(IdRef::Int64(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(IdRef::Int64(v as i64))
+    }
+}
+
+impl<'de> Deserialize<'de> for IdRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(IdRefVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borrows_strings() {
+        let ids: Vec<IdRef> = serde_json::from_str(r#"["user-1", "a\"b", 5]"#).unwrap();
+        assert!(matches!(ids[0], IdRef::String(Cow::Borrowed("user-1"))));
+        assert!(matches!(&ids[1], IdRef::String(Cow::Owned(s)) if s == "a\"b"));
+        assert_eq!(ids[2], IdRef::Int64(5));
+    }
+
+    #[test]
+    fn test_matches_owned_deserialization() {
+        let json = r#"[
+            {"$oid": "5eaefffa00c9fdf000c46fdc"},
+            "$oid:5eaefffa00c9fdf000c46fdc",
+            "$oid:not_valid",
+            {"$numberLong": "12"},
+            -3
+        ]"#;
+        let borrowed: Vec<IdRef> = serde_json::from_str(json).unwrap();
+        let owned: Vec<ID> = serde_json::from_str(json).unwrap();
+        assert_eq!(borrowed, owned);
+        let converted: Vec<ID> = borrowed.into_iter().map(IdRef::into_owned).collect();
+        assert_eq!(converted, owned);
+    }
+
+    #[test]
+    fn test_rejects_unknown_maps() {
+        assert!(serde_json::from_str::<IdRef>(r#"{"$oid": "zz"}"#).is_err());
+        assert!(serde_json::from_str::<IdRef>(r#"{"name": "a"}"#).is_err());
+        assert!(
+            serde_json::from_str::<IdRef>(r#"{"$oid": "5eaefffa00c9fdf000c46fdc", "x": 1}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_id() {
+        let id = ID::with_string("a");
+        assert_eq!(IdRef::from(&id), IdRef::String(Cow::Borrowed("a")));
+        assert_eq!(ID::from(IdRef::from(&id)), id);
+    }
+
+    #[test]
+    fn test_bson() {
+        let id = ID::with_oid(ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap());
+        let bson = id.to_bson();
+        let id_ref = IdRef::deserialize(bson::Deserializer::new(bson)).unwrap();
+        assert_eq!(id_ref, id);
+    }
+}
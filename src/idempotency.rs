@@ -0,0 +1,49 @@
+//! HMAC-derived idempotency keys for operations on an ID
+
+use crate::ID;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+impl ID {
+    /// A stable idempotency key for the operation `scope` on this entity
+    ///
+    /// The lowercase hex HMAC-SHA256 of the compact byte form (see
+    /// [`ID::to_bytes`]) keyed with `scope`, e.g. `"charge"` or
+    /// `"refund:2020-05"`. The same ID and scope always give the same 64
+    /// characters, and Int64 and String IDs with the same digits give
+    /// different keys. The scope is not secret, so the key reveals as much as
+    /// a hash of the ID.
+    pub fn idempotency_key(&self, scope: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(scope.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(&self.to_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ID;
+
+    #[test]
+    fn test_idempotency_key() {
+        let key = ID::Int64(42).idempotency_key("charge");
+        assert_eq!(
+            key,
+            "c6841a25f5e56381cf0db4180a51f329c01a24dd1feec93980814afce1b04e6c"
+        );
+        assert_eq!(ID::Int64(42).idempotency_key("charge"), key);
+        assert_eq!(
+            ID::Int64(42).idempotency_key("refund"),
+            "1bdbdf90af7446b94116bab7d7e2aaa8359dbb831d126a45de909be5ea6f11ae"
+        );
+        assert_eq!(
+            ID::with_string("42").idempotency_key("charge"),
+            "0733002b0e8aea200ca025b26b196b44b12b432199d74ec9c33e7bda47852a8c"
+        );
+    }
+}
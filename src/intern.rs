@@ -0,0 +1,173 @@
+//! Deduplicating the strings of String IDs
+
+use crate::ID;
+use bson::oid::ObjectId;
+use fnv::FnvHashSet;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// An ID whose string payload is shared with every other equal interned ID
+///
+/// Cloning is cheap for every variant.
+#[derive(Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(not(feature = "redact-debug"), derive(Debug))]
+pub enum InternedId {
+    ObjectId(ObjectId),
+    String(Arc<str>),
+    Int64(i64),
+}
+
+impl From<InternedId> for ID {
+    fn from(id: InternedId) -> ID {
+        match id {
+            InternedId::ObjectId(o) => ID::ObjectId(o),
+            InternedId::String(s) => ID::String(s.to_string()),
+            InternedId::Int64(i) => ID::Int64(i),
+        }
+    }
+}
+
+impl fmt::Display for InternedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InternedId::ObjectId(o) => crate::write_oid(f, o),
+            InternedId::String(s) => f.write_str(s),
+            InternedId::Int64(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for InternedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InternedId::ObjectId(o) => f.debug_tuple("ObjectId").field(o).finish(),
+            InternedId::String(s) => f
+                .debug_tuple("String")
+                .field(&crate::redact::RedactedStr(s))
+                .finish(),
+            InternedId::Int64(i) => f.debug_tuple("Int64").field(i).finish(),
+        }
+    }
+}
+
+/// Hit statistics of an [`IdInterner`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct InternerStats {
+    /// Lookups that found an existing string
+    pub hits: u64,
+    /// Lookups that stored a new string
+    pub misses: u64,
+}
+
+impl InternerStats {
+    /// Fraction of lookups that found an existing string, 0 before any lookup
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// A thread-safe pool deduplicating the strings of String IDs
+#[derive(Default)]
+#[cfg_attr(not(feature = "redact-debug"), derive(Debug))]
+pub struct IdInterner {
+    strings: Mutex<FnvHashSet<Arc<str>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl IdInterner {
+    pub fn new() -> Self {
+        IdInterner::default()
+    }
+
+    /// The shared copy of `s`, stored on first use
+    pub fn intern_str(&self, s: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap();
+        if let Some(existing) = strings.get(s) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return existing.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let interned: Arc<str> = Arc::from(s);
+        strings.insert(interned.clone());
+        interned
+    }
+
+    /// Number of distinct strings stored
+    pub fn len(&self) -> usize {
+        self.strings.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> InternerStats {
+        InternerStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for IdInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdInterner")
+            .field("len", &self.len())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl ID {
+    /// Convert to an `InternedId`, sharing the string of String IDs through `interner`
+    pub fn intern(&self, interner: &IdInterner) -> InternedId {
+        match self {
+            ID::ObjectId(o) => InternedId::ObjectId(o.clone()),
+            ID::String(s) => InternedId::String(interner.intern_str(s)),
+            ID::Int64(i) => InternedId::Int64(*i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let interner = IdInterner::new();
+        let a = ID::with_string("user-1").intern(&interner);
+        let b = ID::with_string("user-1").intern(&interner);
+        match (&a, &b) {
+            (InternedId::String(a), InternedId::String(b)) => assert!(Arc::ptr_eq(a, b)),
+            _ => panic!("expected interned strings"),
+        }
+        assert_eq!(ID::from(a), ID::with_string("user-1"));
+        assert_eq!(ID::with_i64(3).intern(&interner), InternedId::Int64(3));
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn test_stats() {
+        let interner = IdInterner::new();
+        assert_eq!(interner.stats().hit_rate(), 0.0);
+        for _ in 0..4 {
+            ID::with_string("a").intern(&interner);
+        }
+        assert_eq!(interner.stats(), InternerStats { hits: 3, misses: 1 });
+        assert_eq!(interner.stats().hit_rate(), 0.75);
+    }
+}
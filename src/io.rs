@@ -0,0 +1,291 @@
+//! Reading and writing files of one ID per line
+//!
+//! Each line holds the first cell of a CSV row, so single column CSV exports
+//! can be read directly. A leading BOM and blank lines are skipped, and cells
+//! may be quoted as in CSV, with `""` for a literal quote.
+use crate::{oid_hex, DecodeIdError, ParseIdError, ID};
+use std::{
+    error, fmt,
+    io::{self, BufRead, Write},
+};
+
+/// How each ID is written on its line
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineFormat {
+    /// The canonical string, parsed back with [`ID::parse_strict`]
+    ///
+    /// Int64 IDs come back as String IDs of their digits.
+    Canonical,
+    /// Lowercase hex of [`ID::to_bytes`], which keeps every variant
+    CompactHex,
+}
+
+/// Why a line couldn't be read as an ID
+#[derive(Debug)]
+pub enum LineError {
+    Io(io::Error),
+    InvalidUtf8,
+    UnterminatedQuote,
+    Id(ParseIdError),
+    InvalidHex,
+    Compact(DecodeIdError),
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineError::Io(e) => fmt::Display::fmt(e, f),
+            LineError::InvalidUtf8 => f.write_str("line is not UTF-8"),
+            LineError::UnterminatedQuote => f.write_str("quoted cell is not terminated"),
+            LineError::Id(e) => fmt::Display::fmt(e, f),
+            LineError::InvalidHex => f.write_str("compact ID is not hex"),
+            LineError::Compact(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+/// A line that couldn't be read, numbered from 1
+#[derive(Debug)]
+pub struct ParseFailureAt {
+    pub line: usize,
+    pub error: LineError,
+}
+
+impl fmt::Display for ParseFailureAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl error::Error for ParseFailureAt {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.error {
+            LineError::Io(e) => Some(e),
+            LineError::Id(e) => Some(e),
+            LineError::Compact(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the IDs of a file, from [`read_ids`] or [`read_ids_as`]
+///
+/// Keeps going after lines that fail to parse, but ends after an I/O error.
+#[derive(Debug)]
+pub struct ReadIds<R> {
+    reader: R,
+    format: LineFormat,
+    line: usize,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+/// Read canonical IDs, one per line
+pub fn read_ids<R: BufRead>(reader: R) -> ReadIds<R> {
+    read_ids_as(reader, LineFormat::Canonical)
+}
+
+/// Read IDs in `format`, one per line
+pub fn read_ids_as<R: BufRead>(reader: R, format: LineFormat) -> ReadIds<R> {
+    ReadIds {
+        reader,
+        format,
+        line: 0,
+        buf: Vec::new(),
+        done: false,
+    }
+}
+
+impl<R: BufRead> Iterator for ReadIds<R> {
+    type Item = Result<ID, ParseFailureAt>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buf.clear();
+            self.line += 1;
+            let fail = |line, error| Some(Err(ParseFailureAt { line, error }));
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    let line = match std::str::from_utf8(&self.buf) {
+                        Ok(line) => line,
+                        Err(_) => return fail(self.line, LineError::InvalidUtf8),
+                    };
+                    let line = line.strip_suffix('\n').unwrap_or(line);
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    let line = match self.line {
+                        1 => line.strip_prefix('\u{feff}').unwrap_or(line),
+                        _ => line,
+                    };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    return match parse_line(line, self.format) {
+                        Ok(id) => Some(Ok(id)),
+                        Err(error) => fail(self.line, error),
+                    };
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.line -= 1,
+                Err(e) => {
+                    self.done = true;
+                    return fail(self.line, LineError::Io(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn parse_line(line: &str, format: LineFormat) -> Result<ID, LineError> {
+    let cell = first_cell(line)?;
+    match format {
+        LineFormat::Canonical => ID::parse_strict(&cell).map_err(LineError::Id),
+        LineFormat::CompactHex => from_compact_hex(&cell),
+    }
+}
+
+/// Decode the [`LineFormat::CompactHex`] form of an ID, in either case
+pub fn from_compact_hex(hex: &str) -> Result<ID, LineError> {
+    let bytes = oid_hex::decode_hex(hex).ok_or(LineError::InvalidHex)?;
+    ID::from_bytes(&bytes).map_err(LineError::Compact)
+}
+
+/// The first cell of a CSV row, unquoted
+fn first_cell(line: &str) -> Result<String, LineError> {
+    let quoted = match line.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => return Ok(line.split(',').next().unwrap_or("").to_string()),
+    };
+    let mut cell = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            cell.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => cell.push('"'),
+            None | Some(',') => return Ok(cell),
+            Some(_) => break,
+        }
+    }
+    Err(LineError::UnterminatedQuote)
+}
+
+/// Writes IDs one per line, for files [`read_ids_as`] reads back
+#[derive(Debug)]
+pub struct IdWriter<W> {
+    writer: W,
+    format: LineFormat,
+}
+
+impl<W: Write> IdWriter<W> {
+    pub fn new(writer: W, format: LineFormat) -> Self {
+        IdWriter { writer, format }
+    }
+
+    /// Write `id` and a newline, quoting canonical strings as CSV needs
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] for String IDs the
+    /// canonical format can't hold: those [`ID::parse_strict`] rejects and
+    /// those containing line breaks.
+    pub fn write_id(&mut self, id: &ID) -> io::Result<()> {
+        match self.format {
+            LineFormat::Canonical => {
+                let canonical = id.to_string();
+                if ID::parse_strict(&canonical).is_err() || canonical.contains(['\r', '\n']) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{:?} has no canonical line form", canonical),
+                    ));
+                }
+                if canonical.contains([',', '"']) || canonical.starts_with('\u{feff}') {
+                    writeln!(self.writer, "\"{}\"", canonical.replace('"', "\"\""))
+                } else {
+                    writeln!(self.writer, "{}", canonical)
+                }
+            }
+            LineFormat::CompactHex => {
+                for byte in id.to_bytes() {
+                    write!(self.writer, "{:02x}", byte)?;
+                }
+                writeln!(self.writer)
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_read_ids() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let input = b"\xef\xbb\xbf$oid:5eaefffa00c9fdf000c46fdc\r\n\n  \nusers/1,extra\n\"a,\"\"b\"\"\",x\n\"open\n$oid:zz\n\xff\n";
+        let read: Vec<_> = read_ids(&input[..]).collect();
+        assert_eq!(read.len(), 6);
+        assert_eq!(read[0].as_ref().unwrap(), &ID::ObjectId(oid));
+        assert_eq!(read[1].as_ref().unwrap(), &ID::with_string("users/1"));
+        assert_eq!(read[2].as_ref().unwrap(), &ID::with_string("a,\"b\""));
+        let failure = read[3].as_ref().unwrap_err();
+        assert_eq!(failure.line, 6);
+        assert!(matches!(failure.error, LineError::UnterminatedQuote));
+        let failure = read[4].as_ref().unwrap_err();
+        assert_eq!(failure.line, 7);
+        assert!(matches!(
+            failure.error,
+            LineError::Id(ParseIdError::InvalidObjectId)
+        ));
+        assert!(matches!(
+            read[5].as_ref().unwrap_err().error,
+            LineError::InvalidUtf8
+        ));
+    }
+
+    #[test]
+    fn test_write_and_read_back() {
+        let ids = vec![
+            ID::ObjectId(ObjectId::new()),
+            ID::with_string("plain"),
+            ID::with_string("with, \"quotes\""),
+            ID::with_string("\u{feff}bom"),
+            ID::Int64(-5),
+        ];
+        for format in [LineFormat::Canonical, LineFormat::CompactHex] {
+            let mut writer = IdWriter::new(Vec::new(), format);
+            for id in &ids {
+                writer.write_id(id).unwrap();
+            }
+            let out = writer.into_inner();
+            let read: Vec<ID> = read_ids_as(&out[..], format)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            let mut expected = ids.clone();
+            if format == LineFormat::Canonical {
+                expected[4] = ID::with_string("-5");
+            }
+            assert_eq!(read, expected);
+        }
+
+        let mut writer = IdWriter::new(Vec::new(), LineFormat::Canonical);
+        for id in &[
+            ID::with_string(""),
+            ID::with_string(" a"),
+            ID::with_string("a\nb"),
+        ] {
+            let e = writer.write_id(id).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert!(writer.into_inner().is_empty());
+    }
+}
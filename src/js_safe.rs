@@ -0,0 +1,55 @@
+//! Serde `with` module writing large Int64 IDs so JavaScript reads them exactly
+//!
+//! Serializes with [`SerializeOptions::js_safe_ints`] and deserializes with
+//! `ID`'s own impl, which reads the `$numberLong` form back.
+//!
+//! ```
+//! use mongodb_id::ID;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Order {
+//!     #[serde(with = "mongodb_id::js_safe")]
+//!     id: ID,
+//! }
+//! ```
+use crate::{SerializeOptions, ID};
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S>(id: &ID, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SerializeOptions::new()
+        .js_safe_ints()
+        .serialize(id, serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<ID, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    ID::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ID;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Order {
+        #[serde(with = "crate::js_safe")]
+        id: ID,
+    }
+
+    #[test]
+    fn test_with() {
+        let order = Order {
+            id: ID::Int64(1 << 53),
+        };
+        let json = serde_json::to_string(&order).unwrap();
+        assert_eq!(json, r#"{"id":{"$numberLong":"9007199254740992"}}"#);
+        assert_eq!(serde_json::from_str::<Order>(&json).unwrap(), order);
+    }
+}
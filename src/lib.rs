@@ -1,20 +1,155 @@
-use mongodb::bson::{oid::ObjectId, Bson};
+//! A MongoDB `_id` that is an ObjectId, an Int64 or a String
+
+use bson::{doc, oid::ObjectId, Bson, Document};
 use serde::{
     de, de::MapAccess, de::Visitor, ser::SerializeMap, Deserialize, Deserializer, Serialize,
     Serializer,
 };
-use std::fmt;
+use std::{convert::TryFrom, error, fmt, str::FromStr};
+
+#[cfg(feature = "anonymize")]
+pub mod anonymize;
+mod arena;
+mod bloom;
+pub mod bulk;
+#[cfg(feature = "cache")]
+mod cache;
+pub mod change_stream;
+pub mod codegen;
+#[cfg(feature = "mongodb")]
+pub mod collection;
+mod collections;
+pub mod compact;
+mod ct;
+mod dbref;
+mod de_options;
+mod diff;
+mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filters;
+pub mod graphql;
+#[cfg(feature = "mongodb")]
+pub mod gridfs;
+pub mod grpc;
+mod hashed;
+mod id_ref;
+#[cfg(feature = "idempotency")]
+mod idempotency;
+mod intern;
+pub mod io;
+pub mod js_safe;
+pub mod map_keys;
+#[cfg(feature = "mongodb")]
+pub mod migrate;
+pub mod model;
+mod oid_hex;
+#[cfg(feature = "pagination")]
+pub mod pagination;
+pub mod pipeline;
+mod preprocess;
+pub mod projection;
+mod provenance;
+mod redact;
+pub mod rejection;
+mod roundtrip;
+#[cfg(feature = "sample")]
+pub mod sample;
+pub mod schema;
+mod scoped;
+mod ser_options;
+mod shard;
+pub mod sort;
+mod stable_hash;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "test-util")]
+pub mod testing;
+mod timestamp;
+pub mod tombstone;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "unicode")]
+mod unicode;
+pub mod update;
+#[cfg(feature = "url")]
+pub mod url;
+mod validate;
+
+pub use arena::IdArena;
+pub use bloom::{IdBloom, IdBloomBuilder};
+#[cfg(feature = "cache")]
+pub use cache::IdCache;
+#[cfg(feature = "mongodb")]
+pub use collection::{ids_from_insert_many, CollectionIdExt, IdQueryError};
+#[cfg(feature = "smallvec")]
+pub use collections::IDList;
+pub use collections::{IDMap, IDSet, Int64IdSet};
+pub use compact::DecodeIdError;
+pub use dbref::DbRef;
+#[cfg(feature = "mongodb")]
+pub use dbref::DbRefResolver;
+pub use de_options::{DeserializeOptions, IdSeed, ObjectIdStrings};
+pub use diff::{diff, IdChange, IdDiff};
+pub use explain::MismatchReason;
+pub use hashed::{HashedId, HashedIdMap, PrecomputedHasher};
+#[cfg(feature = "hashbrown")]
+pub use hashed::{HashedIdMapExt, RawHashedIdMap};
+pub use id_ref::IdRef;
+pub use intern::{IdInterner, InternedId, InternerStats};
+pub use model::WithId;
+pub use preprocess::{dedup, partition_by_variant, PartitionedIds};
+pub use provenance::{ProvenancedId, Source};
+pub use redact::Redacted;
+pub use roundtrip::{Format, RoundtripError};
+pub use scoped::{ScopedId, ScopedIdError, Tenant, TenantId, TypedScopedId};
+pub use ser_options::{SerializeOptions, WithOptions, JS_MAX_SAFE_INTEGER};
+pub use tombstone::Tombstoned;
+pub use validate::{IdPolicy, ValidationError};
 
 /// An ID as defined by the GraphQL specification
 ///
 /// Represented as a string, but can be converted _to_ from an integer as well.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(not(feature = "redact-debug"), derive(Debug))]
 pub enum ID {
     ObjectId(ObjectId),
     String(String),
     Int64(i64),
 }
 
+// `ID` fits in the size of its `String` variant thanks to the niche in
+// `String`'s capacity. Any variant added later must keep its payload within
+// this (e.g. by boxing it) so `Vec<ID>` and friends don't grow.
+const _: () = assert!(std::mem::size_of::<ID>() <= 24);
+
+/// Enabled by one of the `default-*` features, picking what a default ID is:
+/// an empty String (`default-string`), `Int64(0)` (`default-int64`) or a newly
+/// generated ObjectId (`default-object-id`)
+///
+/// Should several be enabled by different crates in one build,
+/// `default-object-id` wins over `default-int64`, which wins over
+/// `default-string`.
+#[cfg(any(
+    feature = "default-string",
+    feature = "default-int64",
+    feature = "default-object-id"
+))]
+impl Default for ID {
+    fn default() -> Self {
+        #[cfg(feature = "default-object-id")]
+        let id = ID::ObjectId(ObjectId::new());
+        #[cfg(all(feature = "default-int64", not(feature = "default-object-id")))]
+        let id = ID::Int64(0);
+        #[cfg(all(
+            feature = "default-string",
+            not(any(feature = "default-int64", feature = "default-object-id"))
+        ))]
+        let id = ID::String(String::new());
+        id
+    }
+}
+
 impl Serialize for ID {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -23,17 +158,18 @@ impl Serialize for ID {
         match self {
             ID::ObjectId(o) => {
                 let mut map = serializer.serialize_map(Some(1))?;
-                map.serialize_entry("$oid", &o.to_string())?;
+                map.serialize_entry("$oid", &format_args!("{}", Hex(o)))?;
                 map.end()
             }
             ID::String(s) => serializer.serialize_str(s),
-            ID::Int64(i) => serializer.serialize_i64(i.clone()),
+            ID::Int64(i) => serializer.serialize_i64(*i),
         }
     }
 }
 
-struct IDVisitor;
-impl<'de> Visitor<'de> for IDVisitor {
+struct IDVisitor<'a>(&'a DeserializeOptions);
+
+impl<'de> Visitor<'de> for IDVisitor<'_> {
     type Value = ID;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -45,23 +181,24 @@ impl<'de> Visitor<'de> for IDVisitor {
         M: MapAccess<'de>,
     {
         // send this back into the Bson deserializer
-        Ok(ID::with_bson(&Bson::deserialize(
-            de::value::MapAccessDeserializer::new(access),
-        )?))
+        let value = de_options::bson_from_map(access, self.0)?;
+        ID::try_from(value).map_err(de::Error::custom)
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(ID::from_string(v))
+        self.0.check_len(v.len())?;
+        Ok(self.0.id_from_string(v.to_string()))
     }
 
     fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(ID::from_string(v))
+        self.0.check_len(v.len())?;
+        Ok(self.0.id_from_string(v))
     }
 
     fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
@@ -75,8 +212,31 @@ impl<'de> Visitor<'de> for IDVisitor {
     where
         E: de::Error,
     {
+        if self.0.strict_integers && v > i64::MAX as u64 {
+            return Err(E::invalid_value(de::Unexpected::Unsigned(v), &self));
+        }
         Ok(ID::Int64(v as i64))
     }
+
+    // binary formats may hand over an ObjectId as its 12 raw bytes
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let mut bytes = [0; 12];
+        if v.len() != bytes.len() {
+            return Err(E::invalid_length(v.len(), &"12 ObjectId bytes"));
+        }
+        bytes.copy_from_slice(v);
+        Ok(ID::ObjectId(ObjectId::with_bytes(bytes)))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_bytes(&v)
+    }
 }
 
 impl<'de> Deserialize<'de> for ID {
@@ -84,13 +244,43 @@ impl<'de> Deserialize<'de> for ID {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(IDVisitor)
+        deserializer.deserialize_any(IDVisitor(&DeserializeOptions::DEFAULT))
+    }
+}
+
+/// Length of `$oid:` followed by 24 hex digits
+const OID_CANONICAL_LEN: usize = 29;
+
+/// Write `$oid:` and the lowercase hex of `bytes` into a stack buffer
+fn canonical_oid(bytes: &[u8; 12]) -> [u8; OID_CANONICAL_LEN] {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut buf = [0; OID_CANONICAL_LEN];
+    buf[..5].copy_from_slice(b"$oid:");
+    for (i, b) in bytes.iter().enumerate() {
+        buf[5 + i * 2] = HEX[usize::from(b >> 4)];
+        buf[6 + i * 2] = HEX[usize::from(b & 0xf)];
+    }
+    buf
+}
+
+/// Write the canonical `$oid:<hex>` form of an ObjectId in a single call
+pub(crate) fn write_oid<W: fmt::Write + ?Sized>(w: &mut W, oid: &ObjectId) -> fmt::Result {
+    // only ASCII was written
+    w.write_str(std::str::from_utf8(&canonical_oid(&oid.bytes())).unwrap())
+}
+
+/// Displays an ObjectId as lowercase hex without allocating
+struct Hex<'a>(&'a ObjectId);
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buf = canonical_oid(&self.0.bytes());
+        f.write_str(std::str::from_utf8(&buf[5..]).unwrap())
     }
 }
 
 impl fmt::Display for ID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        self.write_canonical(f)
     }
 }
 
@@ -103,13 +293,25 @@ impl From<String> for ID {
 impl From<ID> for String {
     fn from(id: ID) -> String {
         match id {
-            ID::ObjectId(o) => format!("$oid:{}", o.to_hex()),
+            ID::ObjectId(o) => {
+                let buf = canonical_oid(&o.bytes());
+                std::str::from_utf8(&buf).unwrap().to_string()
+            }
             ID::String(s) => s,
             ID::Int64(i) => i.to_string(),
         }
     }
 }
 
+/// Parses with [`ID::parse_strict`]
+impl FromStr for ID {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<ID, ParseIdError> {
+        ID::parse_strict(s)
+    }
+}
+
 impl From<i64> for ID {
     fn from(i: i64) -> ID {
         ID::Int64(i)
@@ -122,16 +324,215 @@ impl From<ObjectId> for ID {
     }
 }
 
+/// Error converting a BSON value whose type can't be represented as an `ID`
+///
+/// With the `redact-debug` feature only the type of the value is shown.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(not(feature = "redact-debug"), derive(Debug))]
+pub struct TryFromBsonError {
+    value: Bson,
+}
+
+impl TryFromBsonError {
+    /// The value that failed to convert
+    pub fn value(&self) -> &Bson {
+        &self.value
+    }
+
+    pub fn into_value(self) -> Bson {
+        self.value
+    }
+}
+
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for TryFromBsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryFromBsonError")
+            .field("element_type", &self.value.element_type())
+            .finish()
+    }
+}
+
+impl fmt::Display for TryFromBsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if cfg!(feature = "redact-debug") {
+            write!(f, "Invalid id type used {:?}", self.value.element_type())
+        } else {
+            write!(f, "Invalid id type used {:?}", self.value)
+        }
+    }
+}
+
+impl error::Error for TryFromBsonError {}
+
+/// Converts ObjectId, String and Int64 values, widening Int32 to Int64
+impl TryFrom<&Bson> for ID {
+    type Error = TryFromBsonError;
+
+    fn try_from(value: &Bson) -> Result<ID, TryFromBsonError> {
+        match value {
+            Bson::ObjectId(o) => Ok(ID::ObjectId(o.clone())),
+            Bson::String(s) => Ok(ID::String(s.clone())),
+            Bson::Int64(i) => Ok(ID::Int64(*i)),
+            Bson::Int32(i) => Ok(ID::Int64(i64::from(*i))),
+            _ => Err(TryFromBsonError {
+                value: value.clone(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Bson> for ID {
+    type Error = TryFromBsonError;
+
+    fn try_from(value: Bson) -> Result<ID, TryFromBsonError> {
+        match value {
+            Bson::ObjectId(o) => Ok(ID::ObjectId(o)),
+            Bson::String(s) => Ok(ID::String(s)),
+            Bson::Int64(i) => Ok(ID::Int64(i)),
+            Bson::Int32(i) => Ok(ID::Int64(i64::from(i))),
+            value => Err(TryFromBsonError { value }),
+        }
+    }
+}
+
+/// Error returned by [`ID::parse_strict`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParseIdError {
+    /// The input was empty
+    Empty,
+    /// The input starts or ends with whitespace
+    Whitespace,
+    /// The input has the `$oid:` prefix but no valid ObjectId hex after it
+    InvalidObjectId,
+}
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParseIdError::Empty => "ID is empty",
+            ParseIdError::Whitespace => "ID has leading or trailing whitespace",
+            ParseIdError::InvalidObjectId => "ID has the $oid: prefix but an invalid ObjectId",
+        })
+    }
+}
+
+impl error::Error for ParseIdError {}
+
+/// Convert every element of a `Bson::Array` into an ID
+///
+/// Fails on the first element that can't be converted, or with the whole value
+/// if it isn't an array.
+pub fn ids_from_bson_array(value: &Bson) -> Result<Vec<ID>, TryFromBsonError> {
+    match value {
+        Bson::Array(array) => {
+            let mut ids = Vec::with_capacity(array.len());
+            for element in array {
+                ids.push(ID::try_from(element)?);
+            }
+            Ok(ids)
+        }
+        _ => Err(TryFromBsonError {
+            value: value.clone(),
+        }),
+    }
+}
+
 impl ID {
     pub fn from_string<S: Into<String>>(value: S) -> Self {
         let s: String = value.into();
-        if s.starts_with("$oid:") {
-            match ObjectId::with_string(&s[5..]) {
-                Ok(oid) => ID::ObjectId(oid),
-                Err(_) => ID::String(s),
+        if let Some(hex) = s.strip_prefix("$oid:") {
+            match oid_hex::parse_oid(hex) {
+                Some(oid) => ID::ObjectId(oid),
+                None => ID::String(s),
             }
         } else {
-            ID::String(s.into())
+            ID::String(s)
+        }
+    }
+
+    /// Parse a canonical string, rejecting input [`ID::from_string`] would
+    /// silently keep as a String ID
+    ///
+    /// Fails on empty input, surrounding whitespace, and `$oid:` followed by
+    /// anything but 24 hex digits.
+    pub fn parse_strict(s: &str) -> Result<Self, ParseIdError> {
+        if s.is_empty() {
+            return Err(ParseIdError::Empty);
+        }
+        if s.trim() != s {
+            return Err(ParseIdError::Whitespace);
+        }
+        match s.strip_prefix("$oid:") {
+            Some(hex) => oid_hex::parse_oid(hex)
+                .map(ID::ObjectId)
+                .ok_or(ParseIdError::InvalidObjectId),
+            None => Ok(ID::String(s.to_string())),
+        }
+    }
+
+    /// Convert a String ID holding a canonical ObjectId string (`"$oid:..."`)
+    /// into the ObjectId it represents
+    ///
+    /// `ID::String` can be constructed directly with such a value, which then
+    /// compares and hashes differently from the equivalent ObjectId.
+    pub fn normalize(self) -> Self {
+        match self {
+            ID::String(s) if s.starts_with("$oid:") => ID::from_string(s),
+            other => other,
+        }
+    }
+
+    /// Compare two IDs as if both had been normalized, without allocating
+    pub fn eq_normalized(&self, other: &ID) -> bool {
+        match (self.normalized_oid(), other.normalized_oid()) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => self == other,
+            _ => false,
+        }
+    }
+
+    fn normalized_oid(&self) -> Option<ObjectId> {
+        match self {
+            ID::ObjectId(o) => Some(o.clone()),
+            ID::String(s) => s.strip_prefix("$oid:").and_then(oid_hex::parse_oid),
+            ID::Int64(_) => None,
+        }
+    }
+
+    pub fn is_object_id(&self) -> bool {
+        matches!(self, ID::ObjectId(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, ID::String(_))
+    }
+
+    pub fn is_int(&self) -> bool {
+        matches!(self, ID::Int64(_))
+    }
+
+    /// `f` applied to the ObjectId, or `None` for other variants
+    pub fn map_object_id<T, F: FnOnce(&ObjectId) -> T>(&self, f: F) -> Option<T> {
+        match self {
+            ID::ObjectId(o) => Some(f(o)),
+            _ => None,
+        }
+    }
+
+    /// `f` applied to the string, or `None` for other variants
+    pub fn map_string<T, F: FnOnce(&str) -> T>(&self, f: F) -> Option<T> {
+        match self {
+            ID::String(s) => Some(f(s)),
+            _ => None,
+        }
+    }
+
+    /// `f` applied to the integer, or `None` for other variants
+    pub fn map_int<T, F: FnOnce(i64) -> T>(&self, f: F) -> Option<T> {
+        match self {
+            ID::Int64(i) => Some(f(*i)),
+            _ => None,
         }
     }
 
@@ -144,6 +545,35 @@ impl ID {
         ID::Int64(value.into())
     }
 
+    /// `const` counterpart to [`ID::with_i64`], so sentinel IDs can live in statics
+    ///
+    /// ```
+    /// use mongodb_id::ID;
+    ///
+    /// static SYSTEM_USER: ID = ID::const_i64(0);
+    /// assert_eq!(SYSTEM_USER, ID::Int64(0));
+    /// ```
+    pub const fn const_i64(value: i64) -> Self {
+        ID::Int64(value)
+    }
+
+    /// A String ID borrowing `value`, so sentinel String IDs can live in statics
+    ///
+    /// `ID` owns its strings, so this returns an [`IdRef`]; it compares equal
+    /// to the `ID` and converts to one with [`IdRef::into_owned`]. The string
+    /// is taken as is, without detecting `$oid:` prefixes.
+    ///
+    /// ```
+    /// use mongodb_id::{IdRef, ID};
+    ///
+    /// static SYSTEM_USER: IdRef<'static> = ID::from_static("system");
+    /// assert_eq!(SYSTEM_USER, ID::with_string("system"));
+    /// assert_eq!(SYSTEM_USER.clone().into_owned(), ID::with_string("system"));
+    /// ```
+    pub const fn from_static(value: &'static str) -> IdRef<'static> {
+        IdRef::String(std::borrow::Cow::Borrowed(value))
+    }
+
     pub fn with_oid(value: ObjectId) -> Self {
         ID::ObjectId(value)
     }
@@ -157,16 +587,56 @@ impl ID {
         }
     }
 
+    /// Convert a slice of IDs into a `Bson::Array`, e.g. for an `$in` operand
+    ///
+    /// Shorthand for collecting [`ID::to_bson`] of each ID.
+    pub fn many_to_bson(ids: &[ID]) -> Bson {
+        Bson::Array(ids.iter().map(ID::to_bson).collect())
+    }
+
+    /// Convert to `Bson`, moving the inner value instead of cloning it
+    pub fn into_bson(self) -> Bson {
+        match self {
+            ID::ObjectId(o) => Bson::ObjectId(o),
+            ID::String(s) => Bson::String(s),
+            ID::Int64(i) => Bson::Int64(i),
+        }
+    }
+
     pub fn to_bson(&self) -> Bson {
         match self {
             ID::ObjectId(o) => Bson::ObjectId(o.clone()),
             ID::String(s) => Bson::String(s.to_string()),
-            ID::Int64(i) => Bson::Int64(i.clone()),
+            ID::Int64(i) => Bson::Int64(*i),
         }
     }
 
-    pub fn to_string(&self) -> String {
-        self.clone().into()
+    /// Write the canonical string form of this ID, as produced by `Display`
+    ///
+    /// ObjectIds are written as `$oid:` followed by their hex, without any
+    /// intermediate allocation.
+    pub fn write_canonical<W: fmt::Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        match self {
+            ID::ObjectId(o) => write_oid(w, o),
+            ID::String(s) => w.write_str(s),
+            ID::Int64(i) => write!(w, "{}", i),
+        }
+    }
+
+    /// Filter matching the document with this ID: `{"_id": <id>}`
+    pub fn filter(&self) -> Document {
+        doc! { "_id": self.to_bson() }
+    }
+
+    /// Update pinning `_id` to this ID if an upsert inserts: `{"$setOnInsert": {"_id": <id>}}`
+    pub fn set_on_insert(&self) -> Document {
+        doc! { "$setOnInsert": self.filter() }
+    }
+}
+
+impl From<ID> for Bson {
+    fn from(id: ID) -> Bson {
+        id.into_bson()
     }
 }
 
@@ -216,9 +686,265 @@ mod tests {
     }
 
     #[test]
+    fn test_convert_id_from_bson() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(
+            ID::try_from(&Bson::ObjectId(oid.clone())),
+            Ok(ID::ObjectId(oid))
+        );
+        assert_eq!(
+            ID::try_from(Bson::String("a".to_string())),
+            Ok(ID::String("a".to_string()))
+        );
+        assert_eq!(ID::try_from(Bson::Int32(3)), Ok(ID::Int64(3)));
+        assert_eq!(
+            Bson::from(ID::with_string("a")),
+            Bson::String("a".to_string())
+        );
+        assert_eq!(ID::with_i64(3).into_bson(), ID::with_i64(3).to_bson());
+        let err = ID::try_from(Bson::Boolean(true)).unwrap_err();
+        assert_eq!(err.value(), &Bson::Boolean(true));
+    }
+
+    #[test]
+    fn test_bson_arrays() {
+        let ids = vec![ID::with_string("a"), ID::with_i64(1)];
+        let array = ID::many_to_bson(&ids);
+        assert_eq!(
+            array,
+            Bson::Array(vec![Bson::String("a".to_string()), Bson::Int64(1)])
+        );
+        assert_eq!(ids_from_bson_array(&array), Ok(ids));
+        assert!(ids_from_bson_array(&Bson::Array(vec![Bson::Null])).is_err());
+        assert!(ids_from_bson_array(&Bson::Int64(1)).is_err());
+    }
+
+    #[test]
+    fn test_canonical_string() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let ids = vec![
+            (ID::ObjectId(oid), "$oid:5eaefffa00c9fdf000c46fdc"),
+            (ID::String("abc".to_string()), "abc"),
+            (ID::Int64(-42), "-42"),
+        ];
+        for (id, expected) in ids {
+            assert_eq!(id.to_string(), expected);
+            let mut written = String::new();
+            id.write_canonical(&mut written).unwrap();
+            assert_eq!(written, expected);
+            assert_eq!(String::from(id), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        assert_eq!(
+            ID::parse_strict("$oid:5eaefffa00c9fdf000c46fdc"),
+            Ok(ID::ObjectId(
+                ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap()
+            ))
+        );
+        assert_eq!(ID::parse_strict("abc"), Ok(ID::String("abc".to_string())));
+        assert_eq!(ID::parse_strict(""), Err(ParseIdError::Empty));
+        assert_eq!("abc".parse::<ID>(), ID::parse_strict("abc"));
+        assert_eq!(" ".parse::<ID>(), Err(ParseIdError::Whitespace));
+        assert_eq!(ID::parse_strict(" abc"), Err(ParseIdError::Whitespace));
+        assert_eq!(
+            ID::parse_strict("$oid:not_valid"),
+            Err(ParseIdError::InvalidObjectId)
+        );
+    }
+
+    #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn test_convert_id_from_number() {
         assert_eq!(ID::from(64 as i64), ID::Int64(64));
         assert_eq!(ID::with_i64(32 as u32), ID::Int64(32));
         assert_eq!(ID::with_i64(8 as u8), ID::Int64(8));
     }
+
+    // flatten, internal tags and untagged enums buffer the input before the
+    // ID sees it, so a BSON ObjectId arrives as a `$oid` map, not as a hint
+    #[test]
+    fn test_deserialize_buffered_bson() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Inner {
+            id: ID,
+            #[serde(deserialize_with = "limited")]
+            owner: ID,
+        }
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Flattened {
+            #[serde(flatten)]
+            inner: Inner,
+            name: String,
+        }
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "type")]
+        enum Tagged {
+            Order { id: ID },
+        }
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Untagged {
+            Order { id: ID },
+        }
+        fn limited<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ID, D::Error> {
+            DeserializeOptions::new()
+                .max_depth(1)
+                .strict_maps(true)
+                .deserialize(deserializer)
+        }
+
+        let oid = ObjectId::new();
+        let ids = vec![
+            Bson::ObjectId(oid.clone()),
+            Bson::String("a".to_string()),
+            Bson::Int64(i64::MAX),
+            Bson::Int32(-1),
+        ];
+        for (bson, id) in ids.into_iter().zip(vec![
+            ID::ObjectId(oid),
+            ID::with_string("a"),
+            ID::Int64(i64::MAX),
+            ID::Int64(-1),
+        ]) {
+            let doc = doc! { "id": bson.clone(), "owner": bson.clone(), "name": "x" };
+            assert_eq!(
+                bson::from_document::<Flattened>(doc).unwrap(),
+                Flattened {
+                    inner: Inner {
+                        id: id.clone(),
+                        owner: id.clone(),
+                    },
+                    name: "x".to_string(),
+                }
+            );
+            let doc = doc! { "type": "Order", "id": bson.clone() };
+            assert_eq!(
+                bson::from_document::<Tagged>(doc).unwrap(),
+                Tagged::Order { id: id.clone() }
+            );
+            let doc = doc! { "id": bson };
+            assert_eq!(
+                bson::from_document::<Untagged>(doc).unwrap(),
+                Untagged::Order { id }
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_bytes() {
+        use serde::de::value::{BytesDeserializer, Error};
+
+        let oid = ObjectId::new();
+        let bytes = oid.bytes();
+        let id = ID::deserialize(BytesDeserializer::<Error>::new(&bytes)).unwrap();
+        assert_eq!(id, ID::ObjectId(oid));
+        let visitor = IDVisitor(&DeserializeOptions::DEFAULT);
+        assert_eq!(visitor.visit_byte_buf::<Error>(bytes.to_vec()).unwrap(), id);
+        let e = ID::deserialize(BytesDeserializer::<Error>::new(&[0; 16])).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid length 16, expected 12 ObjectId bytes"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_rejects_unsupported_maps() {
+        for input in &[
+            r#"{"$numberLong": "abc"}"#,
+            r#"{"$numberDouble": "1.5"}"#,
+            r#"{"$oid": 5}"#,
+            r#"{"$oid": "zz"}"#,
+            r#"{"$date": {"$numberLong": "0"}}"#,
+            r#"{"a": 1}"#,
+            r#"{}"#,
+        ] {
+            assert!(serde_json::from_str::<ID>(input).is_err(), "{}", input);
+        }
+        assert_eq!(
+            serde_json::from_str::<ID>(r#"{"$numberLong": 5}"#).is_ok(),
+            cfg!(feature = "legacy-ejson")
+        );
+        assert_eq!(
+            serde_json::from_str::<ID>(r#"{"$numberLong": "5"}"#).unwrap(),
+            ID::Int64(5)
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let hex = "5eaefffa00c9fdf000c46fdc";
+        let oid = ID::ObjectId(ObjectId::with_string(hex).unwrap());
+        let string = ID::String(format!("$oid:{}", hex));
+        assert_ne!(string, oid);
+        assert!(string.eq_normalized(&oid));
+        assert!(oid.eq_normalized(&string));
+        assert_eq!(string.normalize(), oid);
+
+        let invalid = ID::with_string("$oid:nope");
+        assert_eq!(invalid.clone().normalize(), invalid);
+        assert!(invalid.eq_normalized(&ID::with_string("$oid:nope")));
+        assert!(!ID::with_string("1").eq_normalized(&ID::Int64(1)));
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ID>();
+        assert_send_sync::<IdRef<'static>>();
+        assert_send_sync::<IDSet>();
+        assert_send_sync::<IDMap<String>>();
+        assert_send_sync::<HashedIdMap<String>>();
+        assert_send_sync::<IdBloom>();
+        assert_send_sync::<IdInterner>();
+        assert_send_sync::<InternedId>();
+        assert_send_sync::<ScopedId>();
+        assert_send_sync::<IdPolicy>();
+        assert_send_sync::<DeserializeOptions>();
+        #[cfg(feature = "cache")]
+        assert_send_sync::<IdCache<String>>();
+        #[cfg(feature = "mongodb")]
+        assert_send_sync::<migrate::MongoIdMapper>();
+    }
+
+    #[cfg(any(
+        feature = "default-string",
+        feature = "default-int64",
+        feature = "default-object-id"
+    ))]
+    #[test]
+    fn test_default() {
+        #[derive(Default)]
+        struct Model {
+            id: ID,
+        }
+        let id = Model::default().id;
+        #[cfg(all(
+            feature = "default-string",
+            not(any(feature = "default-int64", feature = "default-object-id"))
+        ))]
+        assert_eq!(id, ID::String(String::new()));
+        #[cfg(all(feature = "default-int64", not(feature = "default-object-id")))]
+        assert_eq!(id, ID::Int64(0));
+        #[cfg(feature = "default-object-id")]
+        assert_ne!(id, Model::default().id);
+    }
+
+    #[test]
+    fn test_predicates() {
+        let oid = ID::from_string("$oid:5eaefffa00c9fdf000c46fdc");
+        assert!(oid.is_object_id() && !oid.is_string() && !oid.is_int());
+        assert!(ID::with_string("a").is_string());
+        assert!(ID::Int64(1).is_int());
+        assert_eq!(
+            oid.map_object_id(ObjectId::to_hex).as_deref(),
+            Some("5eaefffa00c9fdf000c46fdc")
+        );
+        assert_eq!(oid.map_string(str::len), None);
+        assert_eq!(ID::with_string("abc").map_string(str::len), Some(3));
+        assert_eq!(ID::Int64(2).map_int(|i| i * 2), Some(4));
+        assert_eq!(ID::with_string("2").map_int(|i| i * 2), None);
+    }
 }
@@ -0,0 +1,198 @@
+//! Serde helpers for maps keyed by `ID`
+//!
+//! JSON and BSON only allow string map keys, and `ID::ObjectId` normally
+//! serializes as an `{"$oid": ...}` map, so `HashMap<ID, V>` fails to serialize.
+//! Use this module with `#[serde(with = "mongodb_id::map_keys")]` to write each
+//! key as a string that keeps its variant instead:
+//!
+//! - ObjectIds as their canonical string, `$oid:<hex>`
+//! - Int64s as `$int:<digits>`
+//! - Strings as they are, except that a leading `$` is doubled, so `"$oid:x"`
+//!   is written as `$$oid:x`
+//!
+//! Every map therefore round-trips, and keys of different IDs never collide.
+//! When reading, keys with an unknown `$` prefix or an invalid payload are
+//! taken as String IDs.
+//!
+//! ```
+//! use mongodb_id::ID;
+//! use serde::{Deserialize, Serialize};
+//! use std::collections::HashMap;
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Counts {
+//!     #[serde(with = "mongodb_id::map_keys")]
+//!     by_user: HashMap<ID, i32>,
+//! }
+//! ```
+use crate::ID;
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{collections::HashMap, fmt, hash::BuildHasher, marker::PhantomData};
+
+const INT_PREFIX: &str = "$int:";
+
+/// Writes an ID in the typed key form without building a `String` first
+pub(crate) struct TypedKey<'a>(pub(crate) &'a ID);
+impl fmt::Display for TypedKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ID::ObjectId(_) => fmt::Display::fmt(self.0, f),
+            ID::Int64(i) => write!(f, "{}{}", INT_PREFIX, i),
+            ID::String(s) if s.starts_with('$') => write!(f, "${}", s),
+            ID::String(s) => f.write_str(s),
+        }
+    }
+}
+impl Serialize for TypedKey<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Read a key written by [`TypedKey`]
+pub(crate) fn parse_key(key: String) -> ID {
+    if key.starts_with("$$") {
+        return ID::String(key[1..].to_string());
+    }
+    if let Some(i) = key.strip_prefix(INT_PREFIX).and_then(|i| i.parse().ok()) {
+        return ID::Int64(i);
+    }
+    ID::from_string(key)
+}
+
+pub fn serialize<V, H, S>(map: &HashMap<ID, V, H>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    V: Serialize,
+    S: Serializer,
+{
+    let mut entries = serializer.serialize_map(Some(map.len()))?;
+    for (id, value) in map {
+        entries.serialize_entry(&TypedKey(id), value)?;
+    }
+    entries.end()
+}
+
+struct KeyedVisitor<V, H>(PhantomData<(V, H)>);
+impl<'de, V, H> Visitor<'de> for KeyedVisitor<V, H>
+where
+    V: Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    type Value = HashMap<ID, V, H>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map keyed by ID strings")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut map =
+            HashMap::with_capacity_and_hasher(access.size_hint().unwrap_or(0), H::default());
+        while let Some((key, value)) = access.next_entry::<String, V>()? {
+            map.insert(parse_key(key), value);
+        }
+        Ok(map)
+    }
+}
+
+pub fn deserialize<'de, V, H, D>(deserializer: D) -> Result<HashMap<ID, V, H>, D::Error>
+where
+    V: Deserialize<'de>,
+    H: BuildHasher + Default,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(KeyedVisitor(PhantomData))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::{doc, oid::ObjectId, Bson};
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Counts {
+        #[serde(with = "crate::map_keys")]
+        by_id: HashMap<ID, i32>,
+    }
+
+    fn counts() -> Counts {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let mut by_id = HashMap::new();
+        by_id.insert(ID::with_oid(oid), 1);
+        Counts { by_id }
+    }
+
+    #[test]
+    fn test_json_map_keys() {
+        let json = serde_json::to_string(&counts()).unwrap();
+        assert_eq!(json, r#"{"by_id":{"$oid:5eaefffa00c9fdf000c46fdc":1}}"#);
+        assert_eq!(serde_json::from_str::<Counts>(&json).unwrap(), counts());
+    }
+
+    #[test]
+    fn test_bson_map_keys() {
+        let bson = bson::to_bson(&counts()).unwrap();
+        assert_eq!(
+            bson,
+            Bson::Document(doc! { "by_id": { "$oid:5eaefffa00c9fdf000c46fdc": 1 } })
+        );
+        assert_eq!(bson::from_bson::<Counts>(bson).unwrap(), counts());
+    }
+
+    #[test]
+    fn test_int64_keys() {
+        let mut by_id = HashMap::new();
+        by_id.insert(ID::Int64(-7), 1);
+        let counts = Counts { by_id };
+        let json = serde_json::to_string(&counts).unwrap();
+        assert_eq!(json, r#"{"by_id":{"$int:-7":1}}"#);
+        assert_eq!(serde_json::from_str::<Counts>(&json).unwrap(), counts);
+        let bson = bson::to_bson(&counts).unwrap();
+        assert_eq!(bson::from_bson::<Counts>(bson).unwrap(), counts);
+    }
+
+    #[test]
+    fn test_colliding_keys() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let ids = [
+            ID::Int64(1),
+            ID::with_string("1"),
+            ID::with_oid(oid),
+            ID::with_string("$oid:5eaefffa00c9fdf000c46fdc"),
+            ID::with_string("$int:1"),
+            ID::with_string("$$int:1"),
+            ID::with_string("$"),
+            ID::with_string(""),
+        ];
+        let by_id: HashMap<ID, i32> = ids.iter().cloned().zip(0..).collect();
+        let counts = Counts { by_id };
+        let json = serde_json::to_value(&counts).unwrap();
+        assert_eq!(json["by_id"].as_object().unwrap().len(), ids.len());
+        assert_eq!(json["by_id"]["$$oid:5eaefffa00c9fdf000c46fdc"], 3);
+        assert_eq!(json["by_id"]["$$$int:1"], 5);
+        assert_eq!(serde_json::from_value::<Counts>(json).unwrap(), counts);
+        let bson = bson::to_bson(&counts).unwrap();
+        assert_eq!(bson::from_bson::<Counts>(bson).unwrap(), counts);
+    }
+
+    #[test]
+    fn test_lenient_keys() {
+        for (key, id) in [
+            ("$int:x", ID::with_string("$int:x")),
+            ("$oid:x", ID::with_string("$oid:x")),
+            ("$other", ID::with_string("$other")),
+            ("1", ID::with_string("1")),
+        ] {
+            assert_eq!(parse_key(key.to_string()), id);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Helpers for migrating integer `_id`s to ObjectIds
+use crate::{IdQueryError, TryFromBsonError, ID};
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, Bson, Document};
+use mongodb::{
+    error::{ErrorKind, WriteFailure},
+    options::{FindOneAndUpdateOptions, FindOneOptions, ReturnDocument},
+    Collection, Database,
+};
+
+/// A persistent mapping between legacy integer IDs and the ObjectIds replacing them
+#[async_trait]
+pub trait IdMapper {
+    /// The ObjectId assigned to `legacy`, if any
+    async fn new_id(&self, legacy: i64) -> Result<Option<ObjectId>, IdQueryError>;
+
+    /// The legacy ID `new` replaced, if any
+    async fn legacy_id(&self, new: &ObjectId) -> Result<Option<i64>, IdQueryError>;
+
+    /// The ObjectId assigned to `legacy`, assigning a new one if there is none
+    ///
+    /// Must return the same ObjectId for every call with the same `legacy`,
+    /// including concurrent ones.
+    async fn assign(&self, legacy: i64) -> Result<ObjectId, IdQueryError>;
+}
+
+/// An [`IdMapper`] storing `{"_id": <new ObjectId>, "legacy": <int>}` documents
+///
+/// Call [`MongoIdMapper::create_indexes`] once before use; the unique index on
+/// `legacy` is what keeps concurrent `assign`s from creating two mappings.
+#[derive(Clone, Debug)]
+pub struct MongoIdMapper {
+    collection: Collection<Document>,
+}
+
+impl MongoIdMapper {
+    pub fn new(collection: Collection<Document>) -> Self {
+        MongoIdMapper { collection }
+    }
+
+    /// Create the unique index on `legacy` in the mapping collection of `db`
+    pub async fn create_indexes(&self, db: &Database) -> mongodb::error::Result<()> {
+        db.run_command(
+            doc! {
+                "createIndexes": self.collection.name(),
+                "indexes": [{ "key": { "legacy": 1 }, "name": "legacy_1", "unique": true }],
+            },
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::CommandError(e) => e.code == 11000,
+        ErrorKind::WriteError(WriteFailure::WriteError(e)) => e.code == 11000,
+        _ => false,
+    }
+}
+
+fn object_id(value: Option<Bson>) -> Result<ObjectId, IdQueryError> {
+    let value = value.unwrap_or(Bson::Null);
+    match value {
+        Bson::ObjectId(o) => Ok(o),
+        value => Err(IdQueryError::Conversion(vec![TryFromBsonError { value }])),
+    }
+}
+
+#[async_trait]
+impl IdMapper for MongoIdMapper {
+    async fn new_id(&self, legacy: i64) -> Result<Option<ObjectId>, IdQueryError> {
+        let options = FindOneOptions::builder()
+            .projection(Some(doc! { "_id": 1 }))
+            .build();
+        match self
+            .collection
+            .find_one(doc! { "legacy": legacy }, options)
+            .await?
+        {
+            Some(mut found) => object_id(found.remove("_id")).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn legacy_id(&self, new: &ObjectId) -> Result<Option<i64>, IdQueryError> {
+        match self
+            .collection
+            .find_one(doc! { "_id": new.clone() }, None)
+            .await?
+        {
+            Some(mut found) => match found.remove("legacy") {
+                Some(Bson::Int64(i)) => Ok(Some(i)),
+                Some(Bson::Int32(i)) => Ok(Some(i.into())),
+                value => Err(IdQueryError::Conversion(vec![TryFromBsonError {
+                    value: value.unwrap_or(Bson::Null),
+                }])),
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn assign(&self, legacy: i64) -> Result<ObjectId, IdQueryError> {
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(Some(true))
+            .return_document(Some(ReturnDocument::After))
+            .build();
+        let upsert = || {
+            self.collection.find_one_and_update(
+                doc! { "legacy": legacy },
+                doc! { "$setOnInsert": { "_id": ObjectId::new() } },
+                options.clone(),
+            )
+        };
+        // of two racing upserts one fails on the unique index, and then finds
+        // the mapping the other inserted
+        let found = match upsert().await {
+            Err(e) if is_duplicate_key(&e) => upsert().await?,
+            found => found?,
+        };
+        object_id(found.and_then(|mut doc| doc.remove("_id")))
+    }
+}
+
+impl ID {
+    /// The ObjectId form of a legacy Int64 ID, assigning one if needed
+    ///
+    /// Other IDs are returned unchanged, since they have been migrated already.
+    pub async fn migrate<M>(&self, mapper: &M) -> Result<ID, IdQueryError>
+    where
+        M: IdMapper + Sync,
+    {
+        match self {
+            ID::Int64(i) => Ok(ID::ObjectId(mapper.assign(*i).await?)),
+            other => Ok(other.clone()),
+        }
+    }
+
+    /// Filter matching the document by either its legacy or its new ID, for
+    /// reads while a collection is partly migrated
+    ///
+    /// Produces `{"_id": {"$in": [<legacy>, <new>]}}`, or just this ID when it
+    /// has no counterpart.
+    pub async fn dual_read_filter<M>(&self, mapper: &M) -> Result<Document, IdQueryError>
+    where
+        M: IdMapper + Sync,
+    {
+        let other = match self {
+            ID::Int64(i) => mapper.new_id(*i).await?.map(ID::ObjectId),
+            ID::ObjectId(o) => mapper.legacy_id(o).await?.map(ID::Int64),
+            ID::String(_) => None,
+        };
+        Ok(match other {
+            Some(other) => crate::filters::any_of(&[self.clone(), other]),
+            None => self.filter(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::{collections::HashMap, sync::Mutex};
+
+    #[derive(Default)]
+    struct MemoryMapper(Mutex<HashMap<i64, ObjectId>>);
+
+    #[async_trait]
+    impl IdMapper for MemoryMapper {
+        async fn new_id(&self, legacy: i64) -> Result<Option<ObjectId>, IdQueryError> {
+            Ok(self.0.lock().unwrap().get(&legacy).cloned())
+        }
+
+        async fn legacy_id(&self, new: &ObjectId) -> Result<Option<i64>, IdQueryError> {
+            let map = self.0.lock().unwrap();
+            Ok(map.iter().find(|(_, o)| *o == new).map(|(i, _)| *i))
+        }
+
+        async fn assign(&self, legacy: i64) -> Result<ObjectId, IdQueryError> {
+            let mut map = self.0.lock().unwrap();
+            Ok(map.entry(legacy).or_default().clone())
+        }
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mapper = MemoryMapper::default();
+        let string = ID::with_string("a");
+        assert_eq!(block_on(string.migrate(&mapper)).unwrap(), string);
+        assert_eq!(
+            block_on(ID::Int64(1).dual_read_filter(&mapper)).unwrap(),
+            ID::Int64(1).filter()
+        );
+
+        let new = block_on(ID::Int64(1).migrate(&mapper)).unwrap();
+        assert!(matches!(new, ID::ObjectId(_)));
+        assert_eq!(block_on(ID::Int64(1).migrate(&mapper)).unwrap(), new);
+        assert_eq!(block_on(new.migrate(&mapper)).unwrap(), new);
+
+        let both = crate::filters::any_of(&[ID::Int64(1), new.clone()]);
+        assert_eq!(
+            block_on(ID::Int64(1).dual_read_filter(&mapper)).unwrap(),
+            both
+        );
+        assert_eq!(
+            block_on(new.dual_read_filter(&mapper)).unwrap(),
+            crate::filters::any_of(&[new, ID::Int64(1)])
+        );
+    }
+}
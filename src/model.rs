@@ -0,0 +1,198 @@
+//! Helpers for structs storing an ID in `_id`
+use crate::ID;
+#[cfg(feature = "derive")]
+pub use mongodb_id_derive::mongo_model;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ops::{Deref, DerefMut};
+
+/// A payload together with the ID of the document it was read from
+///
+/// Serializes as the payload's fields plus `_id`, and reads the ID from either
+/// `_id` or `id`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct WithId<T> {
+    #[serde(rename = "_id", alias = "id")]
+    pub id: ID,
+    #[serde(flatten)]
+    pub inner: T,
+}
+
+impl<T> WithId<T> {
+    pub fn new(id: ID, inner: T) -> Self {
+        WithId { id, inner }
+    }
+
+    /// Replace the payload, keeping the ID
+    pub fn map_inner<U, F: FnOnce(T) -> U>(self, f: F) -> WithId<U> {
+        WithId {
+            id: self.id,
+            inner: f(self.inner),
+        }
+    }
+
+    pub fn into_parts(self) -> (ID, T) {
+        (self.id, self.inner)
+    }
+}
+
+impl<T> Deref for WithId<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for WithId<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+/// Serialize `value` to JSON with its top level `_id` key renamed to `id`
+///
+/// For APIs exposing models that store their ID as `_id`, e.g. through
+/// [`mongo_model`]. Serializers can't tell BSON from JSON output, so the
+/// rename happens on the JSON value, and only at the top level; nested models
+/// keep `_id`. `#[mongo_model(json = "id")]` instead generates a JSON view type
+/// that serializes the ID as `id` wherever it appears.
+pub fn to_json_with_id<T: Serialize>(value: &T) -> serde_json::Result<Value> {
+    let mut json = serde_json::to_value(value)?;
+    if let Value::Object(map) = &mut json {
+        if let Some(id) = map.remove("_id") {
+            map.insert("id".to_string(), id);
+        }
+    }
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+    use bson::oid::ObjectId;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_json_with_id() {
+        let doc = doc! { "_id": 1_i64, "name": "a" };
+        assert_eq!(
+            to_json_with_id(&doc).unwrap(),
+            json!({ "id": 1, "name": "a" })
+        );
+        assert_eq!(to_json_with_id(&ID::Int64(1)).unwrap(), json!(1));
+    }
+
+    #[test]
+    fn test_with_id() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Payload {
+            name: String,
+        }
+
+        let oid = ObjectId::new();
+        let with_id = WithId::new(
+            ID::ObjectId(oid.clone()),
+            Payload {
+                name: "a".to_string(),
+            },
+        );
+        assert_eq!(with_id.name, "a");
+        let doc = bson::to_document(&with_id).unwrap();
+        assert_eq!(doc, doc! { "_id": oid.clone(), "name": "a" });
+        assert_eq!(
+            bson::from_document::<WithId<Payload>>(doc).unwrap(),
+            with_id
+        );
+        let json = format!(r#"{{"id": {{"$oid": "{}"}}, "name": "a"}}"#, oid);
+        assert_eq!(
+            serde_json::from_str::<WithId<Payload>>(&json).unwrap(),
+            with_id
+        );
+
+        let (id, name) = with_id.map_inner(|p| p.name).into_parts();
+        assert_eq!((id, name.as_str()), (ID::ObjectId(oid), "a"));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_mongo_model() {
+        #[mongo_model]
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct User {
+            id: ID,
+            name: String,
+        }
+
+        #[mongo_model]
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Order {
+            #[mongo_id]
+            key: ID,
+            #[serde(default)]
+            note: Option<String>,
+        }
+
+        let user = User {
+            id: ID::Int64(1),
+            name: "a".to_string(),
+        };
+        assert_eq!(
+            bson::to_document(&user).unwrap(),
+            doc! { "_id": 1_i64, "name": "a" }
+        );
+        assert_eq!(
+            to_json_with_id(&user).unwrap(),
+            json!({ "id": 1, "name": "a" })
+        );
+        for json in &[r#"{"_id": 1, "name": "a"}"#, r#"{"id": 1, "name": "a"}"#] {
+            assert_eq!(serde_json::from_str::<User>(json).unwrap(), user);
+        }
+
+        let order: Order = serde_json::from_str(r#"{"_id": "o1"}"#).unwrap();
+        assert_eq!(order.key, ID::with_string("o1"));
+        assert_eq!(order.note, None);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_mongo_model_json_view() {
+        #[mongo_model(json = "id")]
+        #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+        struct Member {
+            #[mongo_id]
+            user: ID,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            role: Option<String>,
+        }
+
+        #[mongo_model(json = "teamId")]
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Team {
+            id: ID,
+            members: Vec<MemberJson>,
+        }
+
+        let member = Member {
+            user: ID::Int64(1),
+            role: None,
+        };
+        assert_eq!(bson::to_document(&member).unwrap(), doc! { "_id": 1_i64 });
+        let view = MemberJson::from(member.clone());
+        assert_eq!(serde_json::to_value(&view).unwrap(), json!({ "id": 1 }));
+        for json in &[r#"{"id": 1}"#, r#"{"_id": 1}"#] {
+            let read: MemberJson = serde_json::from_str(json).unwrap();
+            assert_eq!(Member::from(read), member);
+        }
+
+        let team = TeamJson::from(Team {
+            id: ID::with_string("t"),
+            members: vec![view],
+        });
+        assert_eq!(
+            serde_json::to_value(&team).unwrap(),
+            json!({ "teamId": "t", "members": [{ "id": 1 }] })
+        );
+    }
+}
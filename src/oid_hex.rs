@@ -0,0 +1,136 @@
+//! Hex encoding and decoding for ObjectIds
+
+use bson::oid::ObjectId;
+
+/// Sentinel for bytes that aren't hex digits
+const INVALID: u8 = 0xff;
+
+/// Value of every byte as a hex digit, either case
+const DECODE: [u8; 256] = {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 10 {
+        table[b'0' as usize + i] = i as u8;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 6 {
+        table[b'a' as usize + i] = 10 + i as u8;
+        table[b'A' as usize + i] = 10 + i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// Decode hex digits of either case into `out`, which must be half as long
+fn decode_into(digits: &[u8], out: &mut [u8]) -> bool {
+    for (byte, pair) in out.iter_mut().zip(digits.chunks_exact(2)) {
+        let high = DECODE[usize::from(pair[0])];
+        let low = DECODE[usize::from(pair[1])];
+        if high == INVALID || low == INVALID {
+            return false;
+        }
+        *byte = high << 4 | low;
+    }
+    true
+}
+
+/// Parse a 24 digit hex string into an ObjectId
+///
+/// Accepts exactly what `ObjectId::with_string` accepts, but decodes through a
+/// lookup table without allocating.
+pub(crate) fn parse_oid(s: &str) -> Option<ObjectId> {
+    let digits = s.as_bytes();
+    if digits.len() != 24 {
+        return None;
+    }
+    let mut bytes = [0; 12];
+    if decode_into(digits, &mut bytes) {
+        Some(ObjectId::with_bytes(bytes))
+    } else {
+        None
+    }
+}
+
+/// Decode a string of hex digit pairs of either case, without signs or
+/// separators
+pub(crate) fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let digits = s.as_bytes();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    let mut bytes = vec![0; digits.len() / 2];
+    if decode_into(digits, &mut bytes) {
+        Some(bytes)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_oid() {
+        let expected = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(
+            parse_oid("5eaefffa00c9fdf000c46fdc"),
+            Some(expected.clone())
+        );
+        assert_eq!(parse_oid("5EAEFFFA00C9FDF000C46FDC"), Some(expected));
+        assert_eq!(parse_oid("5eaefffa00c9fdf000c46fd"), None);
+        assert_eq!(parse_oid("5eaefffa00c9fdf000c46fdcc"), None);
+        assert_eq!(parse_oid("5eaefffa00c9fdf000c46fdg"), None);
+        assert_eq!(parse_oid("5eaefffa00c9fdf000c46fé"), None);
+        assert_eq!(parse_oid(""), None);
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("00fF10"), Some(vec![0, 0xff, 0x10]));
+        assert_eq!(decode_hex(""), Some(vec![]));
+        for invalid in ["0", "+a", "0g", "é0", " 00"] {
+            assert_eq!(decode_hex(invalid), None, "{}", invalid);
+        }
+    }
+
+    /// Differential test against the bson implementation over random inputs
+    /// biased towards almost-valid strings
+    #[test]
+    fn test_matches_bson_parsing() {
+        const ALPHABET: &[u8] = b"0123456789abcdefABCDEFgG $:-\xc3\xa9";
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..100_000 {
+            let len = 20 + (next() % 9) as usize;
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect();
+            let s = String::from_utf8_lossy(&bytes);
+            assert_eq!(
+                parse_oid(&s),
+                ObjectId::with_string(&s).ok(),
+                "mismatch for {:?}",
+                s
+            );
+        }
+        for _ in 0..10_000 {
+            let s: String = (0..24)
+                .map(|_| ALPHABET[(next() % 22) as usize] as char)
+                .collect();
+            assert_eq!(
+                parse_oid(&s),
+                ObjectId::with_string(&s).ok(),
+                "mismatch for {:?}",
+                s
+            );
+        }
+    }
+}
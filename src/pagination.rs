@@ -0,0 +1,293 @@
+//! `next`/`prev` links for pages of results ordered by `_id`
+//!
+//! A cursor is the base64url encoded compact form of an ID (see
+//! [`ID::to_bytes`]), passed in an `after` or `before` query parameter.
+//!
+//! ```
+//! use mongodb_id::{pagination::{Cursor, PageLinks}, ID};
+//!
+//! let links = PageLinks::new("https://api.example.com/users?limit=2", &[ID::Int64(1), ID::Int64(2)]);
+//! let header = links.to_link_header().unwrap();
+//! let next = PageLinks::parse_link_header(&header).next.unwrap();
+//! assert_eq!(Cursor::from_url(&next).unwrap(), Some(Cursor::After(ID::Int64(2))));
+//! ```
+use crate::{DecodeIdError, ID};
+use base64::URL_SAFE_NO_PAD;
+use bson::{doc, Document};
+use std::{error, fmt};
+
+/// Error returned when reading a [`Cursor`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CursorError {
+    /// The cursor isn't base64url
+    InvalidBase64,
+    /// The decoded cursor isn't a compact ID
+    Id(DecodeIdError),
+    /// The URL has both an `after` and a `before` cursor
+    Conflicting,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CursorError::InvalidBase64 => f.write_str("cursor is not base64url"),
+            CursorError::Id(e) => write!(f, "invalid cursor: {}", e),
+            CursorError::Conflicting => f.write_str("URL has both an after and a before cursor"),
+        }
+    }
+}
+
+impl error::Error for CursorError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CursorError::Id(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Encode `id` as an opaque, URL safe cursor
+pub fn encode_cursor(id: &ID) -> String {
+    base64::encode_config(id.to_bytes(), URL_SAFE_NO_PAD)
+}
+
+/// Decode a cursor produced by [`encode_cursor`]
+pub fn decode_cursor(cursor: &str) -> Result<ID, CursorError> {
+    let bytes =
+        base64::decode_config(cursor, URL_SAFE_NO_PAD).map_err(|_| CursorError::InvalidBase64)?;
+    ID::from_bytes(&bytes).map_err(CursorError::Id)
+}
+
+/// Where a requested page starts
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Cursor {
+    /// The page after this ID, from a `next` link
+    After(ID),
+    /// The page before this ID, from a `prev` link
+    Before(ID),
+}
+
+impl Cursor {
+    /// Read the `after` or `before` query parameter of `url`
+    ///
+    /// Returns `None` for the first page, when neither is present.
+    pub fn from_url(url: &str) -> Result<Option<Cursor>, CursorError> {
+        let query = match url.split('#').next().and_then(|u| u.split_once('?')) {
+            Some((_, query)) => query,
+            None => return Ok(None),
+        };
+        let mut cursor = None;
+        for pair in query.split('&') {
+            let parsed = match pair.split_once('=') {
+                Some(("after", value)) => Cursor::After(decode_cursor(value)?),
+                Some(("before", value)) => Cursor::Before(decode_cursor(value)?),
+                _ => continue,
+            };
+            if cursor.replace(parsed).is_some() {
+                return Err(CursorError::Conflicting);
+            }
+        }
+        Ok(cursor)
+    }
+
+    /// Filter selecting the documents of the page, to be sorted by `_id`
+    ///
+    /// `{"_id": {"$gt": <id>}}` after an ID. Before one it's
+    /// `{"_id": {"$lt": <id>}}`, where the page is the last documents matching
+    /// it in ascending order: sort descending and reverse the results.
+    pub fn to_filter(&self) -> Document {
+        match self {
+            Cursor::After(id) => doc! { "_id": { "$gt": id.to_bson() } },
+            Cursor::Before(id) => doc! { "_id": { "$lt": id.to_bson() } },
+        }
+    }
+}
+
+/// Links to the pages around a page of IDs
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PageLinks {
+    pub next: Option<String>,
+    pub prev: Option<String>,
+}
+
+impl PageLinks {
+    /// Links for `page`, the IDs of one page in ascending order, served at
+    /// `base_url`
+    ///
+    /// `next` continues after the last ID and `prev` ends before the first;
+    /// an empty page has neither. Any `after` or `before` parameter of
+    /// `base_url` is replaced and other parameters are kept.
+    pub fn new(base_url: &str, page: &[ID]) -> Self {
+        let link = |name: &str, id: &ID| with_param(base_url, name, &encode_cursor(id));
+        PageLinks {
+            next: page.last().map(|id| link("after", id)),
+            prev: page.first().map(|id| link("before", id)),
+        }
+    }
+
+    /// The value of an RFC 8288 `Link` header, or `None` without links
+    ///
+    /// E.g. `<https://api.example.com/users?after=AgAAAAAAAAAC>; rel="next"`.
+    pub fn to_link_header(&self) -> Option<String> {
+        let links: Vec<String> = [("next", &self.next), ("prev", &self.prev)]
+            .iter()
+            .filter_map(|(rel, url)| {
+                url.as_ref()
+                    .map(|url| format!("<{}>; rel=\"{}\"", url, rel))
+            })
+            .collect();
+        if links.is_empty() {
+            None
+        } else {
+            Some(links.join(", "))
+        }
+    }
+
+    /// Read the `next` and `prev` links of a `Link` header, ignoring others
+    ///
+    /// `previous` is accepted for `prev`.
+    pub fn parse_link_header(header: &str) -> Self {
+        let mut links = PageLinks::default();
+        for link in header.split(',') {
+            let mut parts = link.split(';').map(str::trim);
+            let url = match parts
+                .next()
+                .and_then(|u| u.strip_prefix('<')?.strip_suffix('>'))
+            {
+                Some(url) => url.to_string(),
+                None => continue,
+            };
+            for param in parts {
+                let rel = match param.split_once('=') {
+                    Some((name, value)) if name.trim().eq_ignore_ascii_case("rel") => {
+                        value.trim().trim_matches('"')
+                    }
+                    _ => continue,
+                };
+                for rel in rel.split_whitespace() {
+                    match rel.to_ascii_lowercase().as_str() {
+                        "next" => links.next = Some(url.clone()),
+                        "prev" | "previous" => links.prev = Some(url.clone()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        links
+    }
+}
+
+/// `url` with the cursor parameter `name` set to `value`
+fn with_param(url: &str, name: &str, value: &str) -> String {
+    let (url, fragment) = match url.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (url, None),
+    };
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or("");
+            !pair.is_empty() && key != "after" && key != "before"
+        })
+        .collect();
+    let param = format!("{}={}", name, value);
+    params.push(&param);
+    let mut url = format!("{}?{}", path, params.join("&"));
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_cursor_encoding() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(encode_cursor(&ID::Int64(2)), "AgAAAAAAAAAC");
+        for id in [ID::ObjectId(oid), ID::Int64(-1), ID::with_string("a/b?c")] {
+            let cursor = encode_cursor(&id);
+            assert!(cursor
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+            assert_eq!(decode_cursor(&cursor).unwrap(), id);
+        }
+        assert_eq!(decode_cursor("a+b"), Err(CursorError::InvalidBase64));
+        assert_eq!(
+            decode_cursor(""),
+            Err(CursorError::Id(DecodeIdError::Empty))
+        );
+    }
+
+    #[test]
+    fn test_page_links() {
+        let page = [ID::Int64(1), ID::Int64(2)];
+        let links = PageLinks::new("https://api.example.com/users?limit=2&after=AQ#top", &page);
+        assert_eq!(
+            links,
+            PageLinks {
+                next: Some("https://api.example.com/users?limit=2&after=AgAAAAAAAAAC#top".into()),
+                prev: Some("https://api.example.com/users?limit=2&before=AgAAAAAAAAAB#top".into()),
+            }
+        );
+        assert_eq!(
+            PageLinks::new("/users", &page[..1]).next.unwrap(),
+            "/users?after=AgAAAAAAAAAB"
+        );
+        assert_eq!(PageLinks::new("/users", &[]), PageLinks::default());
+        assert_eq!(PageLinks::default().to_link_header(), None);
+
+        let header = links.to_link_header().unwrap();
+        assert_eq!(
+            header,
+            "<https://api.example.com/users?limit=2&after=AgAAAAAAAAAC#top>; rel=\"next\", \
+             <https://api.example.com/users?limit=2&before=AgAAAAAAAAAB#top>; rel=\"prev\""
+        );
+        assert_eq!(PageLinks::parse_link_header(&header), links);
+        assert_eq!(
+            Cursor::from_url(links.next.as_ref().unwrap()).unwrap(),
+            Some(Cursor::After(ID::Int64(2)))
+        );
+        assert_eq!(
+            Cursor::from_url(links.prev.as_ref().unwrap()).unwrap(),
+            Some(Cursor::Before(ID::Int64(1)))
+        );
+    }
+
+    #[test]
+    fn test_parse_link_header() {
+        let links = PageLinks::parse_link_header(
+            r#"</a?page=1>; rel="first", </b?after=x>;REL=next, </c>; title="x"; rel="previous last""#,
+        );
+        assert_eq!(links.next.as_deref(), Some("/b?after=x"));
+        assert_eq!(links.prev.as_deref(), Some("/c"));
+        assert_eq!(PageLinks::parse_link_header(""), PageLinks::default());
+    }
+
+    #[test]
+    fn test_cursor_from_url() {
+        assert_eq!(Cursor::from_url("/users").unwrap(), None);
+        assert_eq!(Cursor::from_url("/users?limit=5#after=AQ").unwrap(), None);
+        assert_eq!(
+            Cursor::from_url("/users?after=AgAAAAAAAAAC&before=AgAAAAAAAAAB"),
+            Err(CursorError::Conflicting)
+        );
+        assert_eq!(
+            Cursor::from_url("/users?after=%%%"),
+            Err(CursorError::InvalidBase64)
+        );
+        assert_eq!(
+            Cursor::After(ID::Int64(2)).to_filter(),
+            doc! { "_id": { "$gt": 2_i64 } }
+        );
+        assert_eq!(
+            Cursor::Before(ID::Int64(2)).to_filter(),
+            doc! { "_id": { "$lt": 2_i64 } }
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Aggregation stages that join and match on `_id`
+
+use crate::{filters, ID};
+use bson::{doc, Bson, Document};
+
+/// The BSON type used to store an `_id` on one side of a join
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IdType {
+    ObjectId,
+    String,
+    Int64,
+}
+
+impl IdType {
+    /// Aggregation expression converting `expr` into this type
+    fn convert(self, expr: Bson) -> Bson {
+        match self {
+            IdType::ObjectId => Bson::Document(doc! { "$toObjectId": expr }),
+            IdType::String => Bson::Document(doc! { "$toString": expr }),
+            IdType::Int64 => Bson::Document(doc! { "$toLong": expr }),
+        }
+    }
+}
+
+impl From<&ID> for IdType {
+    fn from(id: &ID) -> IdType {
+        match id {
+            ID::ObjectId(_) => IdType::ObjectId,
+            ID::String(_) => IdType::String,
+            ID::Int64(_) => IdType::Int64,
+        }
+    }
+}
+
+/// `$lookup` stage joining `local_field` against `_id` in the `from` collection
+///
+/// Both sides must store the ID using the same BSON type, see
+/// [`lookup_by_id_coerced`] otherwise.
+pub fn lookup_by_id(from: &str, local_field: &str, as_: &str) -> Document {
+    doc! {
+        "$lookup": {
+            "from": from,
+            "localField": local_field,
+            "foreignField": "_id",
+            "as": as_,
+        }
+    }
+}
+
+/// `$lookup` stage joining `local_field` against `_id` in the `from` collection,
+/// converting the local value when the two sides store IDs as different types
+///
+/// e.g. a string `user_id` referencing an ObjectId `_id` is wrapped in `$toObjectId`.
+pub fn lookup_by_id_coerced(
+    from: &str,
+    local_field: &str,
+    local: IdType,
+    foreign: IdType,
+    as_: &str,
+) -> Document {
+    if local == foreign {
+        return lookup_by_id(from, local_field, as_);
+    }
+    let local_value = Bson::String(format!("${}", local_field));
+    doc! {
+        "$lookup": {
+            "from": from,
+            "let": { "local_id": foreign.convert(local_value) },
+            "pipeline": [
+                { "$match": { "$expr": { "$eq": ["$_id", "$$local_id"] } } },
+            ],
+            "as": as_,
+        }
+    }
+}
+
+/// `$match` stage selecting documents whose `_id` is any of `ids`
+pub fn match_ids(ids: &[ID]) -> Document {
+    doc! { "$match": filters::any_of(ids) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_same_type() {
+        assert_eq!(
+            lookup_by_id_coerced("users", "user_id", IdType::String, IdType::String, "user"),
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "user_id",
+                    "foreignField": "_id",
+                    "as": "user",
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_lookup_coerced() {
+        assert_eq!(
+            lookup_by_id_coerced("users", "user_id", IdType::String, IdType::ObjectId, "user"),
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "let": { "local_id": { "$toObjectId": "$user_id" } },
+                    "pipeline": [
+                        { "$match": { "$expr": { "$eq": ["$_id", "$$local_id"] } } },
+                    ],
+                    "as": "user",
+                }
+            }
+        );
+        let stage =
+            lookup_by_id_coerced("users", "user_id", IdType::ObjectId, IdType::String, "user");
+        assert_eq!(
+            stage
+                .get_document("$lookup")
+                .unwrap()
+                .get_document("let")
+                .unwrap(),
+            &doc! { "local_id": { "$toString": "$user_id" } }
+        );
+    }
+
+    #[test]
+    fn test_match_ids() {
+        assert_eq!(
+            match_ids(&[ID::with_i64(1)]),
+            doc! { "$match": { "_id": { "$in": [1_i64] } } }
+        );
+    }
+}
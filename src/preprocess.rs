@@ -0,0 +1,72 @@
+//! Deduplicating and partitioning batches of IDs before querying
+
+use crate::ID;
+use bson::oid::ObjectId;
+use fnv::FnvHashSet;
+
+/// Remove duplicate IDs, keeping the first occurrence of each in place
+pub fn dedup(ids: &mut Vec<ID>) {
+    let mut seen = FnvHashSet::with_capacity_and_hasher(ids.len(), Default::default());
+    ids.retain(|id| seen.insert(id.clone()));
+}
+
+/// IDs split up by variant, see [`partition_by_variant`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PartitionedIds {
+    pub object_ids: Vec<ObjectId>,
+    pub strings: Vec<String>,
+    pub ints: Vec<i64>,
+}
+
+/// Split `ids` by variant, keeping their relative order within each variant
+pub fn partition_by_variant(ids: Vec<ID>) -> PartitionedIds {
+    let mut partitioned = PartitionedIds::default();
+    for id in ids {
+        match id {
+            ID::ObjectId(o) => partitioned.object_ids.push(o),
+            ID::String(s) => partitioned.strings.push(s),
+            ID::Int64(i) => partitioned.ints.push(i),
+        }
+    }
+    partitioned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup() {
+        let mut ids = vec![
+            ID::with_string("b"),
+            ID::with_i64(1),
+            ID::with_string("b"),
+            ID::with_string("a"),
+            ID::with_i64(1),
+        ];
+        dedup(&mut ids);
+        assert_eq!(
+            ids,
+            vec![ID::with_string("b"), ID::with_i64(1), ID::with_string("a")]
+        );
+    }
+
+    #[test]
+    fn test_partition_by_variant() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let partitioned = partition_by_variant(vec![
+            ID::with_i64(2),
+            ID::with_string("a"),
+            ID::with_oid(oid.clone()),
+            ID::with_i64(1),
+        ]);
+        assert_eq!(
+            partitioned,
+            PartitionedIds {
+                object_ids: vec![oid],
+                strings: vec!["a".to_string()],
+                ints: vec![2, 1],
+            }
+        );
+    }
+}
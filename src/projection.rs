@@ -0,0 +1,18 @@
+//! Projections over the `_id` field
+
+use bson::{doc, Document};
+
+/// Projection returning only the `_id` of each document: `{"_id": 1}`
+pub fn id_only() -> Document {
+    doc! { "_id": 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_only() {
+        assert_eq!(id_only(), doc! { "_id": 1 });
+    }
+}
@@ -0,0 +1,138 @@
+//! IDs tagged with where they came from and when they were received
+
+use crate::ID;
+use bson::oid::ObjectId;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Where a [`ProvenancedId`] came from
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// Sent by a client, so not to be trusted as unique or well formed
+    ClientSupplied,
+    /// Generated by this service
+    ServerGenerated,
+    /// Taken from another system, named here
+    Imported(String),
+}
+
+/// An ID together with where and when it was received, for audit logs
+///
+/// `received_at` serializes as milliseconds since the Unix epoch.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ProvenancedId {
+    pub id: ID,
+    pub source: Source,
+    #[serde(with = "millis")]
+    pub received_at: SystemTime,
+}
+
+impl ProvenancedId {
+    /// Record `id` as received from `source` now
+    pub fn new(id: ID, source: Source) -> Self {
+        ProvenancedId {
+            id,
+            source,
+            received_at: SystemTime::now(),
+        }
+    }
+
+    /// A new ObjectId generated by this service
+    pub fn generate() -> Self {
+        ProvenancedId::new(ID::ObjectId(ObjectId::new()), Source::ServerGenerated)
+    }
+
+    pub fn into_id(self) -> ID {
+        self.id
+    }
+}
+
+impl From<ProvenancedId> for ID {
+    fn from(provenanced: ProvenancedId) -> ID {
+        provenanced.id
+    }
+}
+
+impl AsRef<ID> for ProvenancedId {
+    fn as_ref(&self) -> &ID {
+        &self.id
+    }
+}
+
+mod millis {
+    use super::*;
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let millis = match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_millis()),
+            Err(e) => i64::try_from(e.duration().as_millis()).map(|m| -m),
+        };
+        millis
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let millis = i64::deserialize(deserializer)?;
+        let offset = Duration::from_millis(millis.unsigned_abs());
+        let time = if millis < 0 {
+            UNIX_EPOCH.checked_sub(offset)
+        } else {
+            UNIX_EPOCH.checked_add(offset)
+        };
+        time.ok_or_else(|| D::Error::custom("timestamp out of range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde() {
+        let provenanced = ProvenancedId {
+            id: ID::with_string("a"),
+            source: Source::Imported("crm".to_string()),
+            received_at: UNIX_EPOCH + Duration::from_millis(1_588_527_098_123),
+        };
+        let json = serde_json::to_value(&provenanced).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "a",
+                "source": { "imported": "crm" },
+                "received_at": 1_588_527_098_123_i64,
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<ProvenancedId>(json).unwrap(),
+            provenanced
+        );
+
+        let before = ProvenancedId {
+            source: Source::ClientSupplied,
+            received_at: UNIX_EPOCH - Duration::from_millis(5),
+            ..provenanced
+        };
+        let json = serde_json::to_string(&before).unwrap();
+        assert!(json.contains(r#""source":"client_supplied","received_at":-5"#));
+        assert_eq!(
+            serde_json::from_str::<ProvenancedId>(&json).unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_into_id() {
+        let generated = ProvenancedId::generate();
+        assert_eq!(generated.source, Source::ServerGenerated);
+        assert!(generated.id.is_object_id());
+        let id = generated.id.clone();
+        assert_eq!(ID::from(generated), id);
+    }
+}
@@ -0,0 +1,135 @@
+//! Display and Debug forms that hide ID values
+
+use crate::ID;
+use md5::{Digest, Md5};
+use std::fmt;
+
+/// Display wrapper for an ID that hides the value of string IDs
+///
+/// ObjectIds and integers are shown as usual. Strings are replaced by
+/// `redacted:` and the first 4 bytes of their MD5 in hex, so the same ID
+/// can still be correlated across log lines. The digest is unsalted, so it
+/// only keeps values out of plain sight; guessable strings can be recovered.
+#[derive(Clone, Copy)]
+pub struct Redacted<'a>(&'a ID);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ID::String(s) => write_redacted(f, s),
+            id => fmt::Display::fmt(id, f),
+        }
+    }
+}
+
+/// Shows the ID the way `Debug` does with the `redact-debug` feature
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug_redacted(self.0, f)
+    }
+}
+
+fn write_redacted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    let digest = Md5::digest(s.as_bytes());
+    write!(
+        f,
+        "redacted:{:02x}{:02x}{:02x}{:02x}",
+        digest[0], digest[1], digest[2], digest[3]
+    )
+}
+
+impl ID {
+    /// Display the ID with string values redacted, for logging
+    pub fn redacted(&self) -> Redacted<'_> {
+        Redacted(self)
+    }
+}
+
+/// With the `redact-debug` feature string IDs are redacted in `Debug` output too
+///
+/// So are the strings of [`IdRef`](crate::IdRef), [`InternedId`](crate::InternedId)
+/// and [`ParseFailure`](crate::bulk::ParseFailure) inputs; an
+/// [`IdInterner`](crate::IdInterner) shows only its size and stats, and a
+/// [`TryFromBsonError`](crate::TryFromBsonError) only the type of its value.
+/// Tenant names and provenance sources aren't IDs and are shown as they are.
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for ID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug_redacted(self, f)
+    }
+}
+
+fn debug_redacted(id: &ID, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match id {
+        ID::ObjectId(o) => f.debug_tuple("ObjectId").field(o).finish(),
+        ID::String(s) => f.debug_tuple("String").field(&RedactedStr(s)).finish(),
+        ID::Int64(i) => f.debug_tuple("Int64").field(i).finish(),
+    }
+}
+
+/// A string that `Debug`-formats as its redacted digest
+pub(crate) struct RedactedStr<'a>(pub(crate) &'a str);
+
+impl fmt::Debug for RedactedStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_redacted(f, self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ID;
+
+    #[test]
+    fn test_redacted() {
+        let email = ID::with_string("someone@example.com");
+        let shown = email.redacted().to_string();
+        assert!(shown.starts_with("redacted:"));
+        assert_eq!(shown.len(), "redacted:".len() + 8);
+        assert!(!shown.contains("someone"));
+        assert_eq!(
+            shown,
+            ID::with_string("someone@example.com")
+                .redacted()
+                .to_string()
+        );
+        assert_ne!(
+            shown,
+            ID::with_string("other@example.com").redacted().to_string()
+        );
+
+        assert_eq!(ID::with_i64(5).redacted().to_string(), "5");
+        let oid = ID::from_string("$oid:5f3d6c0b9e2d4c1a2b3c4d5e");
+        assert_eq!(oid.redacted().to_string(), oid.to_string());
+
+        let debug = format!("{:?}", email.redacted());
+        assert_eq!(debug, format!("String({})", shown));
+        assert_eq!(format!("{:?}", ID::with_i64(5).redacted()), "Int64(5)");
+    }
+
+    #[cfg(feature = "redact-debug")]
+    #[test]
+    fn test_redacted_debug() {
+        use std::convert::TryFrom;
+
+        let debug = format!("{:?}", ID::with_string("someone@example.com"));
+        assert!(debug.starts_with("String(redacted:"));
+        assert!(!debug.contains("someone"));
+        assert_eq!(format!("{:?}", ID::with_i64(5)), "Int64(5)");
+
+        let id = ID::with_string("someone@example.com");
+        let redacted = format!("String({})", id.redacted());
+        assert_eq!(format!("{:?}", crate::IdRef::from(&id)), redacted);
+        let interner = crate::IdInterner::new();
+        assert_eq!(format!("{:?}", id.intern(&interner)), redacted);
+        assert!(!format!("{:?}", interner).contains("someone"));
+
+        let (_, failures) = crate::bulk::parse(vec![" someone@example.com"]);
+        assert!(!format!("{:?}", failures).contains("someone"));
+
+        let secret = bson::Bson::Document(bson::doc! { "email": "someone@example.com" });
+        let err = ID::try_from(secret).unwrap_err();
+        assert!(!format!("{:?}", err).contains("someone"));
+        assert!(!err.to_string().contains("someone"));
+    }
+}
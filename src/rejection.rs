@@ -0,0 +1,119 @@
+//! Framework-independent HTTP responses for requests naming an invalid ID
+//!
+//! `ID` deserializes from plain strings, so path and query extractors accept
+//! it as is. When one fails, answer with [`STATUS`] and an [`InvalidIdBody`]:
+//!
+//! ```
+//! use mongodb_id::{rejection::{InvalidIdBody, STATUS}, ID};
+//!
+//! let error = ID::parse_strict(" a").unwrap_err();
+//! let body = InvalidIdBody::from(error).param("id");
+//! assert_eq!(STATUS, 400);
+//! assert_eq!(
+//!     serde_json::to_string(&body).unwrap(),
+//!     r#"{"error":"INVALID_ID","message":"ID has leading or trailing whitespace","param":"id"}"#
+//! );
+//! ```
+#[cfg(feature = "url")]
+use crate::url::PathError;
+use crate::{ParseIdError, ValidationError};
+use serde::{Deserialize, Serialize};
+
+/// Error code of [`InvalidIdBody`]
+pub const INVALID_ID: &str = "INVALID_ID";
+
+/// HTTP status for an invalid ID: 400 Bad Request
+pub const STATUS: u16 = 400;
+
+/// JSON body of a response rejecting an invalid ID
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InvalidIdBody {
+    /// Always [`INVALID_ID`]
+    pub error: String,
+    pub message: String,
+    /// Name of the path segment or query parameter holding the ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub param: Option<String>,
+}
+
+impl InvalidIdBody {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        InvalidIdBody {
+            error: INVALID_ID.to_string(),
+            message: message.into(),
+            param: None,
+        }
+    }
+
+    pub fn param<S: Into<String>>(mut self, name: S) -> Self {
+        self.param = Some(name.into());
+        self
+    }
+}
+
+impl From<ParseIdError> for InvalidIdBody {
+    fn from(error: ParseIdError) -> Self {
+        InvalidIdBody::new(error.to_string())
+    }
+}
+
+impl From<ValidationError> for InvalidIdBody {
+    fn from(error: ValidationError) -> Self {
+        InvalidIdBody::new(error.to_string())
+    }
+}
+
+#[cfg(feature = "url")]
+impl From<PathError> for InvalidIdBody {
+    fn from(error: PathError) -> Self {
+        InvalidIdBody::new(error.to_string())
+    }
+}
+
+/// HTTP status for a failed [`url::parse_path`](crate::url::parse_path)
+///
+/// 404 Not Found when the path doesn't name an entity at all, [`STATUS`]
+/// otherwise.
+#[cfg(feature = "url")]
+pub fn path_status(error: &PathError) -> u16 {
+    match error {
+        PathError::NotAnEntityPath => 404,
+        _ => STATUS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pipeline::IdType, IdPolicy, ID};
+
+    #[test]
+    fn test_body() {
+        let body = InvalidIdBody::from(ParseIdError::Empty);
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "error": "INVALID_ID", "message": "ID is empty" })
+        );
+        assert_eq!(serde_json::from_value::<InvalidIdBody>(json).unwrap(), body);
+
+        let policy = IdPolicy::new().allow_types(&[IdType::Int64]);
+        let error = ID::with_string("a").validate(&policy).unwrap_err();
+        let body = InvalidIdBody::from(error.clone()).param("user_id");
+        assert_eq!(body.message, error.to_string());
+        assert_eq!(body.param.as_deref(), Some("user_id"));
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_path_status() {
+        let error = crate::url::parse_path("/users").unwrap_err();
+        assert_eq!(path_status(&error), 404);
+        let error = crate::url::parse_path("/users/%20a").unwrap_err();
+        assert_eq!(path_status(&error), STATUS);
+        assert_eq!(
+            InvalidIdBody::from(error.clone()).message,
+            error.to_string()
+        );
+    }
+}
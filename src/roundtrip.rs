@@ -0,0 +1,175 @@
+//! Checking that an ID survives a trip through a wire format
+
+use crate::ID;
+use std::{error, fmt};
+
+/// A format an ID is round tripped through
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Json,
+    Bson,
+    CanonicalString,
+    CompactBytes,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Bson => "BSON",
+            Format::CanonicalString => "canonical string",
+            Format::CompactBytes => "compact bytes",
+        })
+    }
+}
+
+/// Error returned by [`roundtrip_all_formats`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoundtripError {
+    Encode { format: Format, message: String },
+    Decode { format: Format, message: String },
+    Mismatch { format: Format, decoded: ID },
+}
+
+impl RoundtripError {
+    pub fn format(&self) -> Format {
+        match self {
+            RoundtripError::Encode { format, .. }
+            | RoundtripError::Decode { format, .. }
+            | RoundtripError::Mismatch { format, .. } => *format,
+        }
+    }
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundtripError::Encode { format, message } => {
+                write!(f, "unable to encode ID as {}: {}", format, message)
+            }
+            RoundtripError::Decode { format, message } => {
+                write!(f, "unable to decode ID from {}: {}", format, message)
+            }
+            RoundtripError::Mismatch { format, decoded } => {
+                write!(f, "ID decoded from {} as {:?}", format, decoded)
+            }
+        }
+    }
+}
+
+impl error::Error for RoundtripError {}
+
+/// Encode `id` in `format` and decode it again
+pub(crate) fn roundtrip(id: &ID, format: Format) -> Result<(), RoundtripError> {
+    let encode = |e: &dyn fmt::Display| RoundtripError::Encode {
+        format,
+        message: e.to_string(),
+    };
+    let decode = |e: &dyn fmt::Display| RoundtripError::Decode {
+        format,
+        message: e.to_string(),
+    };
+    let decoded = match format {
+        Format::Json => {
+            let json = serde_json::to_string(id).map_err(|e| encode(&e))?;
+            serde_json::from_str(&json).map_err(|e| decode(&e))?
+        }
+        Format::Bson => {
+            let bson = bson::to_bson(id).map_err(|e| encode(&e))?;
+            bson::from_bson(bson).map_err(|e| decode(&e))?
+        }
+        Format::CanonicalString => ID::parse_strict(&id.to_string()).map_err(|e| decode(&e))?,
+        Format::CompactBytes => ID::from_bytes(&id.to_bytes()).map_err(|e| decode(&e))?,
+    };
+    if decoded == *id {
+        Ok(())
+    } else {
+        Err(RoundtripError::Mismatch { format, decoded })
+    }
+}
+
+impl ID {
+    /// Whether encoding the ID in `format` and decoding it gives back an equal ID
+    ///
+    /// | Variant  | JSON | BSON | Canonical string | Compact bytes |
+    /// |----------|------|------|------------------|---------------|
+    /// | ObjectId | yes  | yes  | yes              | yes           |
+    /// | String   | yes¹ | yes¹ | yes²             | yes           |
+    /// | Int64    | yes  | yes  | no³              | yes           |
+    ///
+    /// 1. Except strings that are a canonical ObjectId string (`$oid:` and 24
+    ///    hex digits), which decode as that ObjectId. [`ID::normalize`] them.
+    /// 2. Except those, empty strings, strings with surrounding whitespace and
+    ///    any other `$oid:` strings, which [`ID::parse_strict`] rejects.
+    /// 3. The canonical string of an integer is its digits, which parse back
+    ///    as a String ID.
+    pub fn roundtrips_via(&self, format: Format) -> bool {
+        roundtrip(self, format).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    const FORMATS: [Format; 4] = [
+        Format::Json,
+        Format::Bson,
+        Format::CanonicalString,
+        Format::CompactBytes,
+    ];
+
+    #[test]
+    fn test_conformance_matrix() {
+        let oid = ObjectId::new();
+        let cases = vec![
+            (ID::ObjectId(oid.clone()), [true, true, true, true]),
+            (ID::with_string("abc"), [true, true, true, true]),
+            (
+                ID::with_string("ünïcödé/with spaces"),
+                [true, true, true, true],
+            ),
+            (ID::with_string(""), [true, true, false, true]),
+            (ID::with_string(" padded"), [true, true, false, true]),
+            (ID::with_string("$oid:nothex"), [true, true, false, true]),
+            (
+                ID::String(format!("$oid:{}", oid)),
+                [false, false, false, true],
+            ),
+            (ID::Int64(0), [true, true, false, true]),
+            (ID::Int64(i64::MIN), [true, true, false, true]),
+            (ID::Int64(i64::MAX), [true, true, false, true]),
+        ];
+        for (id, expected) in cases {
+            for (format, expected) in FORMATS.iter().zip(expected.iter()) {
+                assert_eq!(
+                    id.roundtrips_via(*format),
+                    *expected,
+                    "{:?} via {}",
+                    id,
+                    format
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_mismatch_reports_decoded_id() {
+        let oid = ObjectId::new();
+        assert_eq!(
+            roundtrip(&ID::String(format!("$oid:{}", oid)), Format::Bson),
+            Err(RoundtripError::Mismatch {
+                format: Format::Bson,
+                decoded: ID::ObjectId(oid),
+            })
+        );
+        assert_eq!(
+            roundtrip(&ID::Int64(7), Format::CanonicalString),
+            Err(RoundtripError::Mismatch {
+                format: Format::CanonicalString,
+                decoded: ID::with_string("7"),
+            })
+        );
+    }
+}
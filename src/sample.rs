@@ -0,0 +1,102 @@
+//! Random IDs for load testing and demo data
+use crate::ID;
+use bson::oid::ObjectId;
+use rand::{
+    distributions::{Alphanumeric, Distribution, Standard},
+    Rng,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Earliest timestamp of the ObjectIds drawn from [`Standard`], 2017-07-14
+const MIN_TS: u32 = 1_500_000_000;
+
+/// `n` random ObjectIds with timestamps uniformly distributed between
+/// `from_ts` and `to_ts` seconds since the Unix epoch, inclusive
+///
+/// Queries for these hit random points of a collection's `_id` index within
+/// the window, without scanning for real IDs first. Panics if `from_ts` is
+/// after `to_ts`.
+pub fn object_id_range(n: usize, from_ts: u32, to_ts: u32) -> Vec<ID> {
+    object_id_range_with(&mut rand::thread_rng(), n, from_ts, to_ts)
+}
+
+/// [`object_id_range`] drawing from `rng`, e.g. a seeded one for repeatable runs
+pub fn object_id_range_with<R: Rng + ?Sized>(
+    rng: &mut R,
+    n: usize,
+    from_ts: u32,
+    to_ts: u32,
+) -> Vec<ID> {
+    assert!(from_ts <= to_ts, "from_ts is after to_ts");
+    (0..n)
+        .map(|_| {
+            let mut bytes = [0; 12];
+            let ts = rng.gen_range(u64::from(from_ts), u64::from(to_ts) + 1) as u32;
+            bytes[..4].copy_from_slice(&ts.to_be_bytes());
+            rng.fill(&mut bytes[4..]);
+            ID::ObjectId(ObjectId::with_bytes(bytes))
+        })
+        .collect()
+}
+
+/// `rng.gen::<ID>()` and `rand::random::<ID>()` draw each variant a third of
+/// the time, e.g. for seed scripts
+///
+/// ObjectIds are timestamped between mid-2017 and now, Int64s are between 1
+/// and 10^9, and Strings are 12 alphanumeric characters.
+impl Distribution<ID> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ID {
+        match rng.gen_range(0, 3) {
+            0 => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(MIN_TS, |d| d.as_secs() as u32)
+                    .max(MIN_TS);
+                object_id_range_with(rng, 1, MIN_TS, now).remove(0)
+            }
+            1 => ID::Int64(rng.gen_range(1, 1_000_000_001)),
+            _ => ID::String(rng.sample_iter(Alphanumeric).take(12).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_id_range() {
+        let ids = object_id_range(1000, 1_500_000_000, 1_500_000_009);
+        assert_eq!(ids.len(), 1000);
+        let mut seen = [0; 10];
+        for id in &ids {
+            let ts = id.timestamp_secs().unwrap();
+            assert!((1_500_000_000..=1_500_000_009).contains(&ts));
+            seen[(ts - 1_500_000_000) as usize] += 1;
+        }
+        assert!(seen.iter().all(|count| *count > 50), "{:?}", seen);
+
+        let same = object_id_range(3, u32::MAX, u32::MAX);
+        assert!(same.iter().all(|id| id.timestamp_secs() == Some(u32::MAX)));
+    }
+
+    #[test]
+    fn test_standard() {
+        let mut seen = [0; 3];
+        for _ in 0..300 {
+            match rand::random::<ID>() {
+                ID::ObjectId(_) => seen[0] += 1,
+                ID::Int64(i) => {
+                    assert!((1..=1_000_000_000).contains(&i));
+                    seen[1] += 1;
+                }
+                ID::String(s) => {
+                    assert_eq!(s.len(), 12);
+                    assert!(s.bytes().all(|b| b.is_ascii_alphanumeric()));
+                    seen[2] += 1;
+                }
+            }
+        }
+        assert!(seen.iter().all(|count| *count > 50), "{:?}", seen);
+    }
+}
@@ -0,0 +1,96 @@
+//! JSON Schema describing every form an `ID` deserializes from
+use serde_json::{json, Value};
+
+/// Pattern of the canonical string form of an ObjectId
+pub const OID_STRING_PATTERN: &str = "^\\$oid:[0-9a-fA-F]{24}$";
+
+/// Avro schema for an optional `ID`, as the JSON a schema registry stores
+///
+/// A union of null, the 12 bytes of an ObjectId, an Int64 and a String. Avro
+/// requires fixed types to be named, here `mongodb_id.ObjectId`.
+pub const AVRO_SCHEMA: &str = r#"["null",{"type":"fixed","name":"ObjectId","namespace":"mongodb_id","size":12},"long","string"]"#;
+
+/// JSON Schema (draft 7) for an `ID`
+///
+/// Uses `anyOf` rather than `oneOf` since canonical ObjectId strings are also
+/// valid plain strings.
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "ID",
+        "anyOf": [
+            {
+                "description": "ObjectId in canonical string form",
+                "type": "string",
+                "pattern": OID_STRING_PATTERN,
+            },
+            {
+                "description": "String ID",
+                "type": "string",
+            },
+            {
+                "description": "Int64 ID",
+                "type": "integer",
+                "minimum": i64::MIN,
+                "maximum": i64::MAX,
+            },
+            {
+                "description": "ObjectId in extended JSON form",
+                "type": "object",
+                "properties": {
+                    "$oid": { "type": "string", "pattern": "^[0-9a-fA-F]{24}$" },
+                },
+                "required": ["$oid"],
+                "additionalProperties": false,
+            },
+            {
+                "description": "Int64 in extended JSON form, see SerializeOptions",
+                "type": "object",
+                "properties": {
+                    "$numberLong": { "type": "string", "pattern": "^-?[0-9]+$" },
+                },
+                "required": ["$numberLong"],
+                "additionalProperties": false,
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ID;
+
+    #[test]
+    fn test_avro_schema() {
+        let schema: Value = serde_json::from_str(AVRO_SCHEMA).unwrap();
+        let branches = schema.as_array().unwrap();
+        assert_eq!(branches[0], "null");
+        let oid = ID::from_string("$oid:5eaefffa00c9fdf000c46fdc");
+        assert_eq!(branches[1]["type"], "fixed");
+        assert_eq!(branches[1]["size"], oid.to_bytes().len() - 1);
+        assert_eq!(&branches[2..], &[json!("long"), json!("string")]);
+    }
+
+    #[test]
+    fn test_serialized_forms_are_described() {
+        let schema = json_schema();
+        let branches = schema["anyOf"].as_array().unwrap();
+        let oid = ID::from_string("$oid:5eaefffa00c9fdf000c46fdc");
+        let json = serde_json::to_value(&oid).unwrap();
+        assert!(json.get("$oid").is_some());
+        assert!(branches
+            .iter()
+            .any(|branch| branch["type"] == "object" && branch["required"][0] == "$oid"));
+        assert!(branches
+            .iter()
+            .any(|branch| branch["pattern"] == OID_STRING_PATTERN));
+
+        let long = crate::SerializeOptions::new().js_safe_ints();
+        let json = serde_json::to_value(long.wrap(&ID::Int64(i64::MIN))).unwrap();
+        assert!(json["$numberLong"].is_string());
+        assert!(branches
+            .iter()
+            .any(|branch| branch["type"] == "object" && branch["required"][0] == "$numberLong"));
+    }
+}
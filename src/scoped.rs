@@ -0,0 +1,372 @@
+//! IDs scoped to a tenant
+
+use crate::{ParseIdError, ID};
+use bson::{doc, Document};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::TryFrom,
+    error, fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+/// The tenant an ID belongs to in a multi-tenant collection
+///
+/// Never empty and never contains `/`, which separates it from the ID in a
+/// [`ScopedId`]'s canonical string.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TenantId(String);
+
+impl TenantId {
+    pub fn new<S: Into<String>>(tenant: S) -> Result<Self, ScopedIdError> {
+        let tenant = tenant.into();
+        if tenant.is_empty() {
+            Err(ScopedIdError::EmptyTenant)
+        } else if tenant.contains('/') {
+            Err(ScopedIdError::InvalidTenant)
+        } else {
+            Ok(TenantId(tenant))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Scope `id` to this tenant
+    pub fn scope(&self, id: ID) -> ScopedId {
+        ScopedId {
+            tenant: self.clone(),
+            id,
+        }
+    }
+
+    /// Filter matching this tenant's documents whose `_id` is any of `ids`
+    ///
+    /// Produces `{<tenant_field>: <tenant>, "_id": {"$in": [...]}}`.
+    pub fn filter_any_of(&self, tenant_field: &str, ids: &[ID]) -> Document {
+        doc! { tenant_field: self.as_str(), "_id": { "$in": ID::many_to_bson(ids) } }
+    }
+}
+
+impl TryFrom<String> for TenantId {
+    type Error = ScopedIdError;
+
+    fn try_from(tenant: String) -> Result<Self, Self::Error> {
+        TenantId::new(tenant)
+    }
+}
+
+impl From<TenantId> for String {
+    fn from(tenant: TenantId) -> String {
+        tenant.0
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An ID together with the tenant it belongs to
+///
+/// Its canonical string is `<tenant>/<id>`, while serde uses
+/// `{"tenant": ..., "id": ...}` so the ID keeps its type. There is no
+/// comparison with a bare [`ID`] and IDs of different tenants are never equal,
+/// so an ID can't be matched without its tenant by accident. For tenants known
+/// at compile time, [`TypedScopedId`] rejects such comparisons while compiling.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ScopedId {
+    pub tenant: TenantId,
+    pub id: ID,
+}
+
+impl ScopedId {
+    pub fn new(tenant: TenantId, id: ID) -> Self {
+        ScopedId { tenant, id }
+    }
+
+    /// Filter matching the document with this ID in its tenant
+    ///
+    /// Produces `{<tenant_field>: <tenant>, "_id": <id>}`.
+    pub fn filter(&self, tenant_field: &str) -> Document {
+        doc! { tenant_field: self.tenant.as_str(), "_id": self.id.to_bson() }
+    }
+}
+
+impl fmt::Display for ScopedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.tenant, self.id)
+    }
+}
+
+impl FromStr for ScopedId {
+    type Err = ScopedIdError;
+
+    /// Parse `<tenant>/<id>`, the ID part strictly as with [`ID::parse_strict`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tenant, id) = s.split_once('/').ok_or(ScopedIdError::MissingSeparator)?;
+        Ok(ScopedId {
+            tenant: TenantId::new(tenant)?,
+            id: ID::parse_strict(id).map_err(ScopedIdError::Id)?,
+        })
+    }
+}
+
+/// A tenant known at compile time, usually a unit struct
+///
+/// ```
+/// use mongodb_id::Tenant;
+///
+/// struct Acme;
+/// impl Tenant for Acme {
+///     const NAME: &'static str = "acme";
+/// }
+/// ```
+pub trait Tenant {
+    /// The tenant's [`TenantId`], which must not be empty or contain `/`
+    const NAME: &'static str;
+}
+
+/// An ID of the compile time tenant `T`
+///
+/// Like a [`ScopedId`], but the tenant is part of the type, so IDs of
+/// different tenants can't even be compared:
+///
+/// ```compile_fail
+/// use mongodb_id::{Tenant, TypedScopedId, ID};
+///
+/// struct Acme;
+/// impl Tenant for Acme {
+///     const NAME: &'static str = "acme";
+/// }
+/// struct Globex;
+/// impl Tenant for Globex {
+///     const NAME: &'static str = "globex";
+/// }
+///
+/// let same = TypedScopedId::<Acme>::new(ID::Int64(1)) == TypedScopedId::<Globex>::new(ID::Int64(1));
+/// ```
+///
+/// Serializes like the equivalent `ScopedId`, and fails to deserialize with
+/// another tenant.
+pub struct TypedScopedId<T: Tenant> {
+    id: ID,
+    tenant: PhantomData<fn() -> T>,
+}
+
+impl<T: Tenant> TypedScopedId<T> {
+    pub fn new(id: ID) -> Self {
+        TypedScopedId {
+            id,
+            tenant: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> &ID {
+        &self.id
+    }
+
+    pub fn into_id(self) -> ID {
+        self.id
+    }
+
+    pub fn tenant(&self) -> &'static str {
+        T::NAME
+    }
+
+    /// Filter matching the document with this ID in its tenant
+    ///
+    /// Produces `{<tenant_field>: <tenant>, "_id": <id>}`.
+    pub fn filter(&self, tenant_field: &str) -> Document {
+        doc! { tenant_field: T::NAME, "_id": self.id.to_bson() }
+    }
+}
+
+impl<T: Tenant> Clone for TypedScopedId<T> {
+    fn clone(&self) -> Self {
+        TypedScopedId::new(self.id.clone())
+    }
+}
+
+impl<T: Tenant> fmt::Debug for TypedScopedId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedScopedId")
+            .field("tenant", &T::NAME)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl<T: Tenant> PartialEq for TypedScopedId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T: Tenant> Eq for TypedScopedId<T> {}
+
+impl<T: Tenant> Hash for TypedScopedId<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T: Tenant> fmt::Display for TypedScopedId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", T::NAME, self.id)
+    }
+}
+
+impl<T: Tenant> From<TypedScopedId<T>> for ScopedId {
+    /// Panics if `T::NAME` isn't a valid [`TenantId`]
+    fn from(typed: TypedScopedId<T>) -> ScopedId {
+        let tenant = TenantId::new(T::NAME).expect("Tenant::NAME is a valid TenantId");
+        ScopedId::new(tenant, typed.id)
+    }
+}
+
+impl<T: Tenant> TryFrom<ScopedId> for TypedScopedId<T> {
+    type Error = ScopedIdError;
+
+    /// Fails with [`ScopedIdError::WrongTenant`] unless the tenant is `T`
+    fn try_from(scoped: ScopedId) -> Result<Self, Self::Error> {
+        if scoped.tenant.as_str() == T::NAME {
+            Ok(TypedScopedId::new(scoped.id))
+        } else {
+            Err(ScopedIdError::WrongTenant)
+        }
+    }
+}
+
+impl<T: Tenant> Serialize for TypedScopedId<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Borrowed<'a> {
+            tenant: &'a str,
+            id: &'a ID,
+        }
+        Borrowed {
+            tenant: T::NAME,
+            id: &self.id,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Tenant> Deserialize<'de> for TypedScopedId<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scoped = ScopedId::deserialize(deserializer)?;
+        TypedScopedId::try_from(scoped).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Error returned for an invalid [`TenantId`] or [`ScopedId`] string
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScopedIdError {
+    EmptyTenant,
+    InvalidTenant,
+    MissingSeparator,
+    Id(ParseIdError),
+    /// A [`ScopedId`] of another tenant was given for a [`TypedScopedId`]
+    WrongTenant,
+}
+
+impl fmt::Display for ScopedIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScopedIdError::EmptyTenant => f.write_str("tenant is empty"),
+            ScopedIdError::InvalidTenant => f.write_str("tenant contains '/'"),
+            ScopedIdError::MissingSeparator => f.write_str("scoped ID is missing '/'"),
+            ScopedIdError::Id(e) => fmt::Display::fmt(e, f),
+            ScopedIdError::WrongTenant => f.write_str("scoped ID belongs to another tenant"),
+        }
+    }
+}
+
+impl error::Error for ScopedIdError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_canonical_string() {
+        let acme = TenantId::new("acme").unwrap();
+        let oid = ObjectId::new();
+        let scoped = acme.scope(ID::ObjectId(oid.clone()));
+        assert_eq!(scoped.to_string(), format!("acme/$oid:{}", oid));
+        assert_eq!(scoped.to_string().parse::<ScopedId>().unwrap(), scoped);
+        assert_eq!(
+            "acme/a/b".parse::<ScopedId>().unwrap(),
+            acme.scope(ID::with_string("a/b"))
+        );
+        assert_eq!(
+            "acme".parse::<ScopedId>(),
+            Err(ScopedIdError::MissingSeparator)
+        );
+        assert_eq!("/x".parse::<ScopedId>(), Err(ScopedIdError::EmptyTenant));
+        assert_eq!(
+            "acme/".parse::<ScopedId>(),
+            Err(ScopedIdError::Id(ParseIdError::Empty))
+        );
+        assert_ne!(
+            acme.scope(ID::Int64(1)),
+            TenantId::new("other").unwrap().scope(ID::Int64(1))
+        );
+    }
+
+    #[test]
+    fn test_serde_and_filters() {
+        let scoped = TenantId::new("acme").unwrap().scope(ID::Int64(7));
+        let json = serde_json::to_value(&scoped).unwrap();
+        assert_eq!(json, serde_json::json!({ "tenant": "acme", "id": 7 }));
+        assert_eq!(serde_json::from_value::<ScopedId>(json).unwrap(), scoped);
+        assert!(serde_json::from_str::<ScopedId>(r#"{"tenant": "a/b", "id": 7}"#).is_err());
+
+        assert_eq!(scoped.filter("org"), doc! { "org": "acme", "_id": 7_i64 });
+        assert_eq!(
+            scoped.tenant.filter_any_of("org", &[ID::Int64(1)]),
+            doc! { "org": "acme", "_id": { "$in": [1_i64] } }
+        );
+    }
+
+    struct Acme;
+    impl Tenant for Acme {
+        const NAME: &'static str = "acme";
+    }
+
+    #[test]
+    fn test_typed_scoped_id() {
+        let typed = TypedScopedId::<Acme>::new(ID::Int64(7));
+        assert_eq!(typed.to_string(), "acme/7");
+        assert_eq!(typed.tenant(), "acme");
+        assert_eq!(typed.clone(), typed);
+        assert_ne!(typed, TypedScopedId::new(ID::Int64(8)));
+        assert_eq!(typed.filter("org"), doc! { "org": "acme", "_id": 7_i64 });
+
+        let scoped = ScopedId::from(typed.clone());
+        assert_eq!(scoped, TenantId::new("acme").unwrap().scope(ID::Int64(7)));
+        assert_eq!(TypedScopedId::<Acme>::try_from(scoped).unwrap(), typed);
+        let other = TenantId::new("other").unwrap().scope(ID::Int64(7));
+        assert_eq!(
+            TypedScopedId::<Acme>::try_from(other),
+            Err(ScopedIdError::WrongTenant)
+        );
+
+        let json = serde_json::to_value(&typed).unwrap();
+        assert_eq!(json, serde_json::json!({ "tenant": "acme", "id": 7 }));
+        assert_eq!(
+            serde_json::from_value::<TypedScopedId<Acme>>(json).unwrap(),
+            typed
+        );
+        assert!(
+            serde_json::from_str::<TypedScopedId<Acme>>(r#"{"tenant": "other", "id": 7}"#).is_err()
+        );
+        assert_eq!(typed.into_id(), ID::Int64(7));
+    }
+}
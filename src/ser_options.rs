@@ -0,0 +1,139 @@
+//! Configurable serialization of IDs
+
+use crate::ID;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+/// `Number.MAX_SAFE_INTEGER`, the largest integer JavaScript numbers hold exactly
+pub const JS_MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// How an ID is written when serializing
+///
+/// The default matches `ID`'s `Serialize` impl. With an integer threshold,
+/// Int64 IDs whose magnitude exceeds it are written as canonical extended
+/// JSON, `{"$numberLong": "<digits>"}`, which `ID`'s `Deserialize` impl reads
+/// back as the same Int64 and JavaScript can't round to a nearby integer.
+/// ObjectIds can also be written as their canonical string, `"$oid:<hex>"`,
+/// for clients that expect every ID to be a plain string.
+///
+/// ```
+/// use mongodb_id::{SerializeOptions, ID};
+///
+/// let options = SerializeOptions::new().js_safe_ints();
+/// let json = serde_json::to_string(&options.wrap(&ID::Int64(1 << 60))).unwrap();
+/// assert_eq!(json, r#"{"$numberLong":"1152921504606846976"}"#);
+/// assert_eq!(serde_json::from_str::<ID>(&json).unwrap(), ID::Int64(1 << 60));
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SerializeOptions {
+    pub(crate) int_threshold: Option<u64>,
+    pub(crate) object_id_strings: bool,
+}
+
+impl SerializeOptions {
+    pub fn new() -> Self {
+        SerializeOptions::default()
+    }
+
+    /// Write Int64 IDs whose magnitude is above `threshold` as `$numberLong`
+    pub fn stringify_ints_above(mut self, threshold: u64) -> Self {
+        self.int_threshold = Some(threshold);
+        self
+    }
+
+    /// [`SerializeOptions::stringify_ints_above`] [`JS_MAX_SAFE_INTEGER`]
+    pub fn js_safe_ints(self) -> Self {
+        self.stringify_ints_above(JS_MAX_SAFE_INTEGER)
+    }
+
+    /// Write ObjectIds as their canonical string instead of `{"$oid": ...}`
+    ///
+    /// `ID`'s `Deserialize` impl reads these back as ObjectIds unless
+    /// [`crate::DeserializeOptions::object_id_strings`] is `Never`. Meant for
+    /// JSON, as BSON output then stores a string rather than an ObjectId.
+    pub fn object_ids_as_strings(mut self) -> Self {
+        self.object_id_strings = true;
+        self
+    }
+
+    /// Serialize `id` applying these options
+    pub fn serialize<S>(&self, id: &ID, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match (id, self.int_threshold) {
+            (ID::Int64(i), Some(threshold)) if i.unsigned_abs() > threshold => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("$numberLong", &format_args!("{}", i))?;
+                map.end()
+            }
+            (ID::ObjectId(_), _) if self.object_id_strings => serializer.collect_str(id),
+            _ => id.serialize(serializer),
+        }
+    }
+
+    /// `id` wrapped to serialize with these options
+    pub fn wrap(self, id: &ID) -> WithOptions<'_> {
+        WithOptions { id, options: self }
+    }
+}
+
+/// An ID serializing with [`SerializeOptions`], from [`SerializeOptions::wrap`]
+#[derive(Clone, Copy, Debug)]
+pub struct WithOptions<'a> {
+    id: &'a ID,
+    options: SerializeOptions,
+}
+
+impl Serialize for WithOptions<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.options.serialize(self.id, serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::Bson;
+
+    #[test]
+    fn test_js_safe_ints() {
+        let options = SerializeOptions::new().js_safe_ints();
+        let max = JS_MAX_SAFE_INTEGER as i64;
+        for i in &[0, max, -max] {
+            let json = serde_json::to_value(options.wrap(&ID::Int64(*i))).unwrap();
+            assert!(json.is_i64());
+        }
+        for i in &[max + 1, -max - 1, i64::MIN, i64::MAX] {
+            let id = ID::Int64(*i);
+            let json = serde_json::to_value(options.wrap(&id)).unwrap();
+            assert_eq!(json, serde_json::json!({ "$numberLong": i.to_string() }));
+            assert_eq!(serde_json::from_value::<ID>(json).unwrap(), id);
+            assert_eq!(bson::to_bson(&options.wrap(&id)).unwrap(), Bson::Int64(*i));
+        }
+
+        let id = ID::Int64(i64::MAX);
+        assert!(serde_json::to_value(SerializeOptions::new().wrap(&id))
+            .unwrap()
+            .is_i64());
+        let small = SerializeOptions::new().stringify_ints_above(9);
+        assert!(serde_json::to_value(small.wrap(&ID::Int64(10)))
+            .unwrap()
+            .is_object());
+        let oid = ID::from_string("$oid:5eaefffa00c9fdf000c46fdc");
+        let strings = SerializeOptions::new().object_ids_as_strings();
+        let json = serde_json::to_value(strings.wrap(&oid)).unwrap();
+        assert_eq!(json, "$oid:5eaefffa00c9fdf000c46fdc");
+        assert_eq!(serde_json::from_value::<ID>(json).unwrap(), oid);
+        assert!(serde_json::to_value(strings.wrap(&ID::Int64(1)))
+            .unwrap()
+            .is_i64());
+        let string = ID::with_string("9007199254740993");
+        assert_eq!(
+            serde_json::to_value(options.wrap(&string)).unwrap(),
+            serde_json::to_value(&string).unwrap()
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! The hashed shard key MongoDB computes for an `_id`
+
+use crate::ID;
+use md5::{Digest, Md5};
+
+// canonical BSON type numbers used by the server when hashing
+const CANONICAL_NUMBER: i32 = 10;
+const CANONICAL_STRING: i32 = 15;
+const CANONICAL_OID: i32 = 35;
+
+impl ID {
+    /// The value MongoDB stores for this ID in a hashed index on `_id`
+    ///
+    /// Matches the server's `convertShardKeyToHashed`: an MD5 over the hash seed
+    /// (0), the canonical BSON type and the element value, of which the low 8
+    /// bytes are read as a little endian integer. Useful for predicting which
+    /// chunk of a hashed sharded collection an ID routes to.
+    pub fn hashed_shard_key(&self) -> i64 {
+        let mut hasher = Md5::new();
+        hasher.update(0_i32.to_le_bytes());
+        match self {
+            ID::ObjectId(o) => {
+                hasher.update(CANONICAL_OID.to_le_bytes());
+                hasher.update(o.bytes());
+            }
+            ID::String(s) => {
+                // BSON string value: int32 length including the trailing NUL, bytes, NUL
+                hasher.update(CANONICAL_STRING.to_le_bytes());
+                hasher.update((s.len() as i32 + 1).to_le_bytes());
+                hasher.update(s.as_bytes());
+                hasher.update([0]);
+            }
+            ID::Int64(i) => {
+                hasher.update(CANONICAL_NUMBER.to_le_bytes());
+                hasher.update(i.to_le_bytes());
+            }
+        }
+        let digest = hasher.finalize();
+        let mut low = [0; 8];
+        low.copy_from_slice(&digest[..8]);
+        i64::from_le_bytes(low)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_hashed_shard_key() {
+        assert_eq!(ID::with_i64(1).hashed_shard_key(), 5902408780260971510);
+        assert_eq!(
+            ID::with_string("abc").hashed_shard_key(),
+            8478485326885698097
+        );
+        let oid = ObjectId::with_string("5b2be413c06d924ab26ff9ca").unwrap();
+        assert_eq!(ID::with_oid(oid).hashed_shard_key(), 2523394966799057167);
+    }
+}
@@ -0,0 +1,41 @@
+//! Sort documents over the `_id` field
+
+use bson::{doc, Document};
+
+/// Sort order of a field
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+impl Direction {
+    fn value(self) -> i32 {
+        match self {
+            Direction::Ascending => 1,
+            Direction::Descending => -1,
+        }
+    }
+}
+
+/// Sort by `_id`: `{"_id": 1}` or `{"_id": -1}`
+///
+/// Extend another sort document with this one to break ties by `_id`.
+pub fn by_id(direction: Direction) -> Document {
+    doc! { "_id": direction.value() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_by_id() {
+        assert_eq!(by_id(Direction::Ascending), doc! { "_id": 1 });
+        assert_eq!(by_id(Direction::Descending), doc! { "_id": -1 });
+
+        let mut sort = doc! { "created_at": -1 };
+        sort.extend(by_id(Direction::Ascending));
+        assert_eq!(sort, doc! { "created_at": -1, "_id": 1 });
+    }
+}
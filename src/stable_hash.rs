@@ -0,0 +1,152 @@
+//! Hashes of IDs that are fixed across releases, platforms and languages
+//!
+//! All of them hash the compact byte form of [`ID::to_bytes`]: a tag byte (1
+//! for ObjectId, 2 for Int64, 3 for String) followed by the 12 ObjectId bytes,
+//! the 8 big endian bytes of the integer or the UTF-8 bytes of the string.
+use crate::ID;
+use fnv::FnvHasher;
+use std::hash::Hasher;
+
+/// The fixed, all-zero key of [`ID::stable_hash64`]
+const SIPHASH_KEY: (u64, u64) = (0, 0);
+
+/// SipHash-2-4 with the key split into two little endian words
+fn siphash24((k0, k1): (u64, u64), bytes: &[u8]) -> u64 {
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+    let round = |v: &mut [u64; 4]| {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    };
+    let compress = |v: &mut [u64; 4], word: u64| {
+        v[3] ^= word;
+        round(v);
+        round(v);
+        v[0] ^= word;
+    };
+
+    let chunks = bytes.chunks_exact(8);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        compress(&mut v, u64::from_le_bytes(word));
+    }
+    let mut last = [0; 8];
+    last[..tail.len()].copy_from_slice(tail);
+    last[7] = bytes.len() as u8;
+    compress(&mut v, u64::from_le_bytes(last));
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+/// 64-bit FNV-1a, which `FnvHasher::write` computes byte by byte
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+impl ID {
+    /// Assign the ID to one of `n` buckets, e.g. worker shards or A/B cohorts
+    ///
+    /// The bucket is the 64-bit FNV-1a hash of the compact byte form modulo
+    /// `n`, so it never changes for a given ID and `n` and is easily computed
+    /// in other languages. Changing `n` moves most IDs to another bucket.
+    /// Panics if `n` is 0.
+    pub fn bucket(&self, n: u32) -> u32 {
+        assert!(n > 0, "bucket count must be positive");
+        (fnv1a64(&self.to_bytes()) % u64::from(n)) as u32
+    }
+
+    /// SipHash-2-4 of the compact byte form with an all-zero 128-bit key
+    ///
+    /// Meant for deriving cache keys identically in other services: SipHash
+    /// libraries exist for most languages. For example `ID::Int64(42)`, encoded
+    /// as `02 00 00 00 00 00 00 00 2a`, hashes to `0x088bb3d08230e315`. The key
+    /// is public, so this gives no protection against hash flooding.
+    pub fn stable_hash64(&self) -> u64 {
+        siphash24(SIPHASH_KEY, &self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_fnv1a64() {
+        // published FNV-1a test vectors
+        assert_eq!(fnv1a64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_siphash24() {
+        // from the SipHash paper, key 00..0f
+        let key = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+        assert_eq!(siphash24(key, b""), 0x726f_db47_dd0e_0e31);
+        let message: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(key, &message), 0xa129_ca61_49be_45e5);
+        let message: Vec<u8> = (0..64).collect();
+        #[allow(deprecated)]
+        let mut std = std::hash::SipHasher::new_with_keys(key.0, key.1);
+        std::hash::Hasher::write(&mut std, &message);
+        assert_eq!(siphash24(key, &message), std::hash::Hasher::finish(&std));
+    }
+
+    #[test]
+    fn test_stable_hash64() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(ID::ObjectId(oid).stable_hash64(), 0x1f43_88fd_2115_93f4);
+        assert_eq!(
+            ID::with_string("users/1").stable_hash64(),
+            0x6972_23ba_b699_861b
+        );
+        assert_eq!(ID::Int64(42).stable_hash64(), 0x088b_b3d0_8230_e315);
+        assert_eq!(ID::with_string("").stable_hash64(), 0x9531_a486_1d0b_4d50);
+    }
+
+    #[test]
+    fn test_bucket() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let ids = [ID::ObjectId(oid), ID::with_string("users/1"), ID::Int64(42)];
+        // vectors for implementations in other languages
+        let buckets: Vec<u32> = ids.iter().map(|id| id.bucket(1000)).collect();
+        assert_eq!(buckets, [352, 18, 631]);
+        for id in &ids {
+            let hash = fnv1a64(&id.to_bytes());
+            assert_eq!(id.bucket(1), 0);
+            assert_eq!(u64::from(id.bucket(16)), hash % 16);
+            assert_eq!(id.bucket(16), id.clone().bucket(16));
+        }
+
+        let mut counts = [0; 4];
+        for i in 0..4000 {
+            counts[ID::Int64(i).bucket(4) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|c| (800..1200).contains(c)),
+            "{:?}",
+            counts
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Helpers for snapshot testing documents that contain generated ObjectIds
+//!
+//! ObjectIds are replaced by placeholders numbered in order of first
+//! appearance, `000000000000000000000000`, `000000000000000000000001`, ..., so
+//! two documents match whenever their IDs are used in the same places.
+use bson::{oid::ObjectId, Bson};
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+#[derive(Default)]
+struct Placeholders(HashMap<[u8; 12], u64>);
+
+impl Placeholders {
+    fn get(&mut self, bytes: [u8; 12]) -> [u8; 12] {
+        let next = self.0.len() as u64;
+        let n = *self.0.entry(bytes).or_insert(next);
+        let mut placeholder = [0; 12];
+        placeholder[4..].copy_from_slice(&n.to_be_bytes());
+        placeholder
+    }
+
+    fn hex(&mut self, hex: &str) -> Option<String> {
+        let oid = crate::oid_hex::parse_oid(hex)?;
+        Some(ObjectId::with_bytes(self.get(oid.bytes())).to_hex())
+    }
+
+    fn json(&mut self, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                if let (1, Some(Value::String(hex))) = (map.len(), map.get("$oid")) {
+                    if let Some(hex) = self.hex(hex) {
+                        return serde_json::json!({ "$oid": hex });
+                    }
+                }
+                Value::Object(map.iter().map(|(k, v)| (k.clone(), self.json(v))).collect())
+            }
+            Value::Array(values) => Value::Array(values.iter().map(|v| self.json(v)).collect()),
+            Value::String(s) => match s.strip_prefix("$oid:").and_then(|hex| self.hex(hex)) {
+                Some(hex) => Value::String(format!("$oid:{}", hex)),
+                None => value.clone(),
+            },
+            _ => value.clone(),
+        }
+    }
+
+    fn bson(&mut self, value: &Bson) -> Bson {
+        match value {
+            Bson::ObjectId(o) => Bson::ObjectId(ObjectId::with_bytes(self.get(o.bytes()))),
+            Bson::Document(doc) => {
+                Bson::Document(doc.iter().map(|(k, v)| (k.clone(), self.bson(v))).collect())
+            }
+            Bson::Array(values) => Bson::Array(values.iter().map(|v| self.bson(v)).collect()),
+            Bson::String(s) => match s.strip_prefix("$oid:").and_then(|hex| self.hex(hex)) {
+                Some(hex) => Bson::String(format!("$oid:{}", hex)),
+                None => value.clone(),
+            },
+            _ => value.clone(),
+        }
+    }
+}
+
+/// Replace the ObjectIds in a JSON value with numbered placeholders
+///
+/// Both `{"$oid": ...}` maps and canonical `$oid:` strings are replaced.
+pub fn normalize_json(value: &Value) -> Value {
+    Placeholders::default().json(value)
+}
+
+/// Replace the ObjectIds in a BSON value with numbered placeholders
+pub fn normalize_bson(value: &Bson) -> Bson {
+    Placeholders::default().bson(value)
+}
+
+/// Serialize `value` to JSON and normalize its ObjectIds
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized.
+pub fn to_normalized_json<T: serde::Serialize>(value: &T) -> Value {
+    normalize_json(&serde_json::to_value(value).expect("value serializes to JSON"))
+}
+
+/// Convert `value` to BSON and normalize its ObjectIds
+///
+/// # Panics
+///
+/// Panics if `value` can't be converted.
+pub fn to_normalized_bson<T: serde::Serialize>(value: &T) -> Bson {
+    normalize_bson(&bson::to_bson(value).expect("value converts to BSON"))
+}
+
+/// Compare the normalized JSON form of `value` with the golden file at `path`
+///
+/// The file is (re)written when it doesn't exist or `UPDATE_GOLDEN` is set.
+///
+/// # Panics
+///
+/// Panics if the file doesn't match, or can't be read or written.
+pub fn assert_golden_json<P: AsRef<Path>, T: serde::Serialize>(path: P, value: &T) {
+    let path = path.as_ref();
+    let actual = serde_json::to_string_pretty(&to_normalized_json(value)).unwrap() + "\n";
+    if std::env::var_os("UPDATE_GOLDEN").is_some() || !path.exists() {
+        fs::write(path, &actual)
+            .unwrap_or_else(|e| panic!("unable to write {}: {}", path.display(), e));
+        return;
+    }
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("unable to read {}: {}", path.display(), e));
+    assert_eq!(
+        actual,
+        expected,
+        "{} is out of date, rerun with UPDATE_GOLDEN=1 to update it",
+        path.display()
+    );
+}
+
+/// Assert two values serialize to the same JSON once ObjectIds are normalized
+#[macro_export]
+macro_rules! assert_id_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        assert_eq!(
+            $crate::test_util::to_normalized_json(&$left),
+            $crate::test_util::to_normalized_json(&$right),
+        )
+    };
+}
+
+/// Assert two values convert to the same BSON once ObjectIds are normalized
+#[macro_export]
+macro_rules! assert_id_bson_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        assert_eq!(
+            $crate::test_util::to_normalized_bson(&$left),
+            $crate::test_util::to_normalized_bson(&$right),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ID;
+    use bson::doc;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_json() {
+        let (a, b) = (ObjectId::new(), ObjectId::new());
+        let value = json!({
+            "_id": ID::ObjectId(a.clone()),
+            "parent": format!("$oid:{}", b),
+            "refs": [ID::ObjectId(b), ID::ObjectId(a), ID::with_string("x")],
+        });
+        assert_eq!(
+            normalize_json(&value),
+            json!({
+                "_id": { "$oid": "000000000000000000000000" },
+                "parent": "$oid:000000000000000000000001",
+                "refs": [
+                    { "$oid": "000000000000000000000001" },
+                    { "$oid": "000000000000000000000000" },
+                    "x",
+                ],
+            })
+        );
+        assert_id_json_eq!(
+            json!({ "_id": ID::ObjectId(ObjectId::new()) }),
+            json!({ "_id": ID::ObjectId(ObjectId::new()) }),
+        );
+    }
+
+    #[test]
+    fn test_normalize_bson() {
+        let a = ObjectId::new();
+        let doc = doc! { "_id": a.clone(), "copy": a, "other": ObjectId::new() };
+        let normalized = normalize_bson(&Bson::Document(doc));
+        let expected = doc! {
+            "_id": ObjectId::with_bytes([0; 12]),
+            "copy": ObjectId::with_bytes([0; 12]),
+            "other": ObjectId::with_bytes([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+        };
+        assert_eq!(normalized, Bson::Document(expected));
+        assert_id_bson_eq!(
+            doc! { "_id": ObjectId::new() },
+            doc! { "_id": ObjectId::new() }
+        );
+    }
+
+    #[test]
+    fn test_golden_json() {
+        let path =
+            std::env::temp_dir().join(format!("mongodb_id-golden-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_golden_json(&path, &json!({ "_id": ID::ObjectId(ObjectId::new()) }));
+        assert_golden_json(&path, &json!({ "_id": ID::ObjectId(ObjectId::new()) }));
+        fs::remove_file(&path).unwrap();
+    }
+}
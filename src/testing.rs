@@ -0,0 +1,54 @@
+//! Round trip checks for downstream test suites
+use crate::roundtrip::roundtrip;
+pub use crate::roundtrip::{Format, RoundtripError};
+use crate::ID;
+
+/// Check `id` survives JSON, BSON, its canonical string and compact bytes
+///
+/// The canonical string is skipped for `Int64` IDs, which intentionally
+/// parse back as strings. See [`ID::roundtrips_via`] for what to expect of
+/// each format.
+pub fn roundtrip_all_formats(id: &ID) -> Result<(), RoundtripError> {
+    roundtrip(id, Format::Json)?;
+    roundtrip(id, Format::Bson)?;
+    if !id.is_int() {
+        roundtrip(id, Format::CanonicalString)?;
+    }
+    roundtrip(id, Format::CompactBytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_roundtrip_all_formats() {
+        for id in &[
+            ID::ObjectId(ObjectId::new()),
+            ID::with_string("abc"),
+            ID::Int64(i64::MIN),
+            ID::Int64(7),
+        ] {
+            assert_eq!(roundtrip_all_formats(id), Ok(()), "{:?}", id);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_failures() {
+        for id in &[ID::with_string(""), ID::with_string("$oid:not-hex")] {
+            let err = roundtrip_all_formats(id).unwrap_err();
+            assert_eq!(err.format(), Format::CanonicalString);
+        }
+
+        let oid = ObjectId::new();
+        let err = roundtrip_all_formats(&ID::String(format!("$oid:{}", oid))).unwrap_err();
+        assert_eq!(
+            err,
+            RoundtripError::Mismatch {
+                format: Format::Json,
+                decoded: ID::ObjectId(oid),
+            }
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Creation times embedded in ObjectIds
+
+use crate::ID;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl ID {
+    /// Seconds since the Unix epoch at which an ObjectId was generated
+    ///
+    /// `None` for String and Int64 IDs, which carry no timestamp.
+    pub fn timestamp_secs(&self) -> Option<u32> {
+        match self {
+            ID::ObjectId(o) => {
+                let bytes = o.bytes();
+                Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            _ => None,
+        }
+    }
+
+    /// [`ID::timestamp_secs`] as a `SystemTime`, needing no date library
+    pub fn system_time(&self) -> Option<SystemTime> {
+        self.timestamp_secs()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs.into()))
+    }
+
+    /// [`ID::timestamp_secs`] as a chrono `DateTime`
+    pub fn chrono_timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.timestamp_secs()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs.into(), 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ID;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_timestamp() {
+        let id = ID::from_string("$oid:5eaefffa00c9fdf000c46fdc");
+        assert_eq!(id.timestamp_secs(), Some(0x5eae_fffa));
+        assert_eq!(
+            id.system_time(),
+            Some(UNIX_EPOCH + Duration::from_secs(0x5eae_fffa))
+        );
+        assert_eq!(ID::Int64(1).timestamp_secs(), None);
+        assert_eq!(ID::with_string("a").system_time(), None);
+    }
+
+    #[test]
+    fn test_chrono_timestamp() {
+        let id = ID::from_string("$oid:5eaefffa00c9fdf000c46fdc");
+        let ts = id.chrono_timestamp().unwrap();
+        assert_eq!(ts.timestamp(), 0x5eae_fffa);
+        assert_eq!(ts.to_rfc3339(), "2020-05-03T17:31:38+00:00");
+    }
+}
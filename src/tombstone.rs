@@ -0,0 +1,103 @@
+//! The soft-delete convention: deleted documents keep their `_id` and get a
+//! `deleted_at` date, and live documents have none
+use crate::ID;
+use bson::{doc, Bson, DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+/// Field holding the deletion date of a soft-deleted document
+pub const DELETED_AT: &str = "deleted_at";
+
+/// The ID of a soft-deleted document and when it was deleted
+///
+/// Serializes as `{"_id": <id>, "deleted_at": <date>}`, the date as a BSON
+/// date.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Tombstoned<I = ID> {
+    #[serde(rename = "_id")]
+    pub id: I,
+    pub deleted_at: DateTime,
+}
+
+impl<I> Tombstoned<I> {
+    /// Record `id` as deleted now
+    pub fn new(id: I) -> Self {
+        Tombstoned::at(id, chrono::Utc::now().into())
+    }
+
+    pub fn at(id: I, deleted_at: DateTime) -> Self {
+        Tombstoned { id, deleted_at }
+    }
+
+    pub fn into_id(self) -> I {
+        self.id
+    }
+
+    /// Update marking the document deleted: `{"$set": {"deleted_at": <date>}}`
+    pub fn to_update(&self) -> Document {
+        doc! { "$set": { DELETED_AT: Bson::DateTime(self.deleted_at.0) } }
+    }
+}
+
+/// Filter matching documents that aren't soft-deleted
+///
+/// Produces `{"deleted_at": null}`, which also matches a missing field.
+pub fn only_live() -> Document {
+    doc! { DELETED_AT: null }
+}
+
+/// Filter matching soft-deleted documents: `{"deleted_at": {"$ne": null}}`
+pub fn only_deleted() -> Document {
+    doc! { DELETED_AT: { "$ne": null } }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+    use chrono::{TimeZone, Utc};
+
+    fn deleted_at() -> DateTime {
+        Utc.timestamp_millis_opt(1_588_527_098_123).unwrap().into()
+    }
+
+    #[test]
+    fn test_bson() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        let tombstoned = Tombstoned::at(ID::ObjectId(oid.clone()), deleted_at());
+        let doc = bson::to_document(&tombstoned).unwrap();
+        assert_eq!(doc, doc! { "_id": oid, "deleted_at": deleted_at().0 });
+        assert_eq!(bson::from_document::<Tombstoned>(doc).unwrap(), tombstoned);
+
+        let doc = doc! { "_id": 1_i64, "deleted_at": deleted_at().0, "name": "a" };
+        let tombstoned: Tombstoned = bson::from_document(doc).unwrap();
+        assert_eq!(tombstoned.into_id(), ID::Int64(1));
+        assert!(bson::from_document::<Tombstoned>(doc! { "_id": 1_i64, "deleted_at": 5 }).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let tombstoned = Tombstoned::at(ID::with_string("a"), deleted_at());
+        let json = serde_json::to_string(&tombstoned).unwrap();
+        assert_eq!(
+            json,
+            r#"{"_id":"a","deleted_at":{"$date":"2020-05-03T17:31:38.123Z"}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Tombstoned>(&json).unwrap(),
+            tombstoned
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let before = Utc::now();
+        let tombstoned = Tombstoned::new(7_i64);
+        assert!(tombstoned.deleted_at.0 >= before);
+        assert_eq!(
+            tombstoned.to_update(),
+            doc! { "$set": { "deleted_at": tombstoned.deleted_at.0 } }
+        );
+        assert_eq!(only_live(), doc! { "deleted_at": Bson::Null });
+        assert_eq!(only_deleted(), doc! { "deleted_at": { "$ne": Bson::Null } });
+    }
+}
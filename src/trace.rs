@@ -0,0 +1,34 @@
+//! Recording IDs as `tracing` field values
+
+use crate::ID;
+use tracing::field::{display, DisplayValue};
+
+impl ID {
+    /// Record the canonical form of the ID as a span or event field
+    ///
+    /// `tracing::Value` is sealed, so this wraps the ID in `tracing`'s display
+    /// recorder; the same as writing `%id`, without allocating.
+    ///
+    /// ```
+    /// let id = mongodb_id::ID::with_i64(7);
+    /// tracing::info!(entity.id = id.as_trace_value(), "loaded");
+    /// ```
+    pub fn as_trace_value(&self) -> DisplayValue<&ID> {
+        display(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ID;
+
+    #[test]
+    fn test_trace_value_is_canonical() {
+        let id = ID::from_string("$oid:5f3d6c0b9e2d4c1a2b3c4d5e");
+        assert_eq!(
+            format!("{:?}", id.as_trace_value()),
+            "$oid:5f3d6c0b9e2d4c1a2b3c4d5e"
+        );
+        assert_eq!(format!("{:?}", ID::with_i64(7).as_trace_value()), "7");
+    }
+}
@@ -0,0 +1,94 @@
+//! Checks for confusable and unnormalized characters in String IDs
+
+use crate::{ParseIdError, ID};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// Latin lookalikes from the Cyrillic and Greek blocks
+const HOMOGLYPHS: &[char] = &[
+    'а', 'е', 'о', 'р', 'с', 'у', 'х', 'і', 'ј', 'ѕ', 'ԁ', 'һ', 'ԛ', 'ԝ', 'А', 'В', 'Е', 'К', 'М',
+    'Н', 'О', 'Р', 'С', 'Т', 'Х', 'І', 'Ј', 'Ѕ', 'Α', 'Β', 'Ε', 'Ζ', 'Η', 'Ι', 'Κ', 'Μ', 'Ν', 'Ο',
+    'Ρ', 'Τ', 'Υ', 'Χ', 'ο', 'ν', 'ι', 'ı',
+];
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+    )
+}
+
+/// The first character of `s` likely to be mistaken for another
+///
+/// Invisible and bidi control characters are always reported, as are
+/// fullwidth forms. Cyrillic and Greek lookalikes of Latin letters are
+/// reported when `s` also contains ASCII letters. This is a short list of the
+/// usual suspects, not the full Unicode confusables table.
+pub(crate) fn find_confusable(s: &str) -> Option<(usize, char)> {
+    let has_ascii_letters = s.bytes().any(|b| b.is_ascii_alphabetic());
+    s.char_indices().find(|&(_, c)| {
+        is_invisible(c)
+            || ('\u{FF01}'..='\u{FF5E}').contains(&c)
+            || (has_ascii_letters && HOMOGLYPHS.contains(&c))
+    })
+}
+
+pub(crate) fn is_normalized(s: &str) -> bool {
+    is_nfc(s)
+}
+
+impl ID {
+    /// [`ID::parse_strict`] after normalizing the input to Unicode NFC
+    ///
+    /// Composed and decomposed spellings of the same string then produce the
+    /// same String ID.
+    pub fn parse_strict_nfc(s: &str) -> Result<Self, ParseIdError> {
+        if is_nfc(s) {
+            ID::parse_strict(s)
+        } else {
+            ID::parse_strict(&s.nfc().collect::<String>())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IdPolicy, ValidationError};
+
+    #[test]
+    fn test_parse_strict_nfc() {
+        let composed = ID::parse_strict_nfc("caf\u{e9}").unwrap();
+        assert_eq!(ID::parse_strict_nfc("cafe\u{301}").unwrap(), composed);
+        assert_ne!(ID::parse_strict("cafe\u{301}").unwrap(), composed);
+    }
+
+    #[test]
+    fn test_unicode_policy() {
+        let policy = IdPolicy::new().require_nfc().reject_confusables();
+        assert!(ID::with_string("caf\u{e9}").validate(&policy).is_ok());
+        assert!(ID::with_string("пароль").validate(&policy).is_ok());
+        assert_eq!(
+            ID::with_string("cafe\u{301}").validate(&policy),
+            Err(ValidationError::NotNormalized)
+        );
+        assert_eq!(
+            ID::with_string("p\u{430}ypal").validate(&policy),
+            Err(ValidationError::Confusable {
+                index: 1,
+                ch: '\u{430}'
+            })
+        );
+        assert_eq!(
+            ID::with_string("abc\u{200B}").validate(&policy),
+            Err(ValidationError::Confusable {
+                index: 3,
+                ch: '\u{200B}'
+            })
+        );
+    }
+}
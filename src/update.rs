@@ -0,0 +1,116 @@
+//! Update documents that leave `_id` untouched
+
+use crate::ID;
+use bson::{doc, Document};
+
+/// Build a `$set` update from `document`, dropping any `_id` it contains
+///
+/// MongoDB rejects updates that modify `_id`, so a whole serialized struct can't
+/// be used as a `$set` payload as-is.
+pub fn set_excluding_id(mut document: Document) -> Document {
+    document.remove("_id");
+    doc! { "$set": document }
+}
+
+/// Build an upsert update that `$set`s `set_doc` and pins `_id` to `id` on insert
+///
+/// Any `_id` in `set_doc` is dropped, since `$set`ting it fails on existing
+/// documents and conflicts with `$setOnInsert` on new ones. `$set` is left
+/// out when nothing else remains, as MongoDB rejects an empty `$set`.
+pub fn upsert_with_id(id: &ID, mut set_doc: Document) -> Document {
+    set_doc.remove("_id");
+    let mut update = if set_doc.is_empty() {
+        Document::new()
+    } else {
+        doc! { "$set": set_doc }
+    };
+    update.extend(id.set_on_insert());
+    update
+}
+
+/// Rename the `from` key of `document` to `to`, keeping its position
+///
+/// Typically used as `rename_id_field(doc, "id", "_id")` for structs that
+/// serialize their identifier as `id`. If `to` is already present it is
+/// dropped in favor of the renamed value, wherever it appears.
+pub fn rename_id_field(document: Document, from: &str, to: &str) -> Document {
+    if from == to || !document.contains_key(from) {
+        return document;
+    }
+    document
+        .into_iter()
+        .filter(|(k, _)| k != to)
+        .map(|(k, v)| {
+            if k == from {
+                (to.to_string(), v)
+            } else {
+                (k, v)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_excluding_id() {
+        assert_eq!(
+            set_excluding_id(doc! { "_id": "a", "name": "b" }),
+            doc! { "$set": { "name": "b" } }
+        );
+        assert_eq!(
+            set_excluding_id(doc! { "name": "b" }),
+            doc! { "$set": { "name": "b" } }
+        );
+    }
+
+    #[test]
+    fn test_set_on_insert() {
+        assert_eq!(
+            ID::with_i64(4).set_on_insert(),
+            doc! { "$setOnInsert": { "_id": 4_i64 } }
+        );
+    }
+
+    #[test]
+    fn test_upsert_with_id() {
+        assert_eq!(
+            upsert_with_id(&ID::with_string("a"), doc! { "_id": "b", "name": "c" }),
+            doc! { "$set": { "name": "c" }, "$setOnInsert": { "_id": "a" } }
+        );
+        assert_eq!(
+            upsert_with_id(&ID::with_string("a"), doc! { "_id": "b" }),
+            doc! { "$setOnInsert": { "_id": "a" } }
+        );
+    }
+
+    #[test]
+    fn test_rename_id_field() {
+        let renamed = rename_id_field(doc! { "name": "b", "id": "a", "age": 3 }, "id", "_id");
+        assert_eq!(renamed, doc! { "name": "b", "_id": "a", "age": 3 });
+        assert_eq!(
+            renamed.keys().collect::<Vec<_>>(),
+            vec!["name", "_id", "age"]
+        );
+
+        for document in [
+            doc! { "id": "renamed", "_id": "orig" },
+            doc! { "_id": "orig", "id": "renamed" },
+        ] {
+            assert_eq!(
+                rename_id_field(document, "id", "_id"),
+                doc! { "_id": "renamed" }
+            );
+        }
+        assert_eq!(
+            rename_id_field(doc! { "_id": "orig" }, "id", "_id"),
+            doc! { "_id": "orig" }
+        );
+        assert_eq!(
+            rename_id_field(doc! { "id": "a" }, "id", "id"),
+            doc! { "id": "a" }
+        );
+    }
+}
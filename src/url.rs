@@ -0,0 +1,254 @@
+//! REST paths for entities, e.g. `/users/$oid:5eaefffa00c9fdf000c46fdc`
+//!
+//! ```
+//! use mongodb_id::{pipeline::IdType, url, ID};
+//!
+//! let path = url::to_path("users", &ID::with_string("a/b"));
+//! assert_eq!(path, "/users/a%2Fb");
+//! assert_eq!(url::parse_path(&path).unwrap(), ("users".to_string(), ID::with_string("a/b")));
+//! assert_eq!(url::parse_path_as("/users/42", IdType::Int64).unwrap().1, ID::Int64(42));
+//! ```
+use crate::{oid_hex, pipeline::IdType, ParseIdError, ID};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::{error, fmt};
+
+/// Everything but the characters allowed in a path segment; `$` and `:` stay
+/// as they are, so canonical ObjectId strings remain readable
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Error returned by [`parse_path`] and [`parse_path_as`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PathError {
+    /// The path isn't of the form `/<collection>/<id>`
+    NotAnEntityPath,
+    /// A segment doesn't decode to UTF-8
+    InvalidEncoding,
+    /// The ID segment isn't a valid canonical string
+    Id(ParseIdError),
+    /// The ID segment doesn't spell an ID of the expected type
+    WrongType(IdType),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::NotAnEntityPath => f.write_str("path is not of the form /<collection>/<id>"),
+            PathError::InvalidEncoding => f.write_str("path segment is not percent-encoded UTF-8"),
+            PathError::Id(e) => write!(f, "invalid ID in path: {}", e),
+            PathError::WrongType(expected) => write!(f, "ID in path is not {:?}", expected),
+        }
+    }
+}
+
+impl error::Error for PathError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PathError::Id(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The path of the entity `id` in `collection`, each segment percent-encoded
+///
+/// The ID is written as its canonical string, so Int64 IDs look like String
+/// IDs; read them back with [`parse_path_as`]. Segments of only dots are
+/// fully escaped, since clients and proxies resolve `.` and `..` otherwise.
+pub fn to_path(collection: &str, id: &ID) -> String {
+    format!("/{}/{}", encode(collection), encode(&id.to_string()))
+}
+
+fn encode(segment: &str) -> String {
+    if !segment.is_empty() && segment.bytes().all(|b| b == b'.') {
+        "%2E".repeat(segment.len())
+    } else {
+        utf8_percent_encode(segment, SEGMENT).to_string()
+    }
+}
+
+/// Split `/<collection>/<id>` into the decoded collection and ID
+///
+/// The ID segment is read with [`ID::parse_strict`], so IDs with surrounding
+/// whitespace are rejected. One trailing slash is allowed.
+pub fn parse_path(path: &str) -> Result<(String, ID), PathError> {
+    let (collection, id) = segments(path)?;
+    let id = ID::parse_strict(&id).map_err(PathError::Id)?;
+    Ok((collection, id))
+}
+
+/// [`parse_path`] for a collection whose IDs are all of type `id_type`
+///
+/// Int64 IDs are read from their decimal digits with an optional `-`, and
+/// ObjectIds with or without the `$oid:` prefix. String IDs are taken as they
+/// are, even if they look like a canonical ObjectId, but empty segments and
+/// surrounding whitespace are still rejected.
+pub fn parse_path_as(path: &str, id_type: IdType) -> Result<(String, ID), PathError> {
+    let (collection, segment) = segments(path)?;
+    let id = match id_type {
+        IdType::Int64 if segment.starts_with('+') => None,
+        IdType::Int64 => segment.parse().ok().map(ID::Int64),
+        IdType::ObjectId => {
+            let hex = segment.strip_prefix("$oid:").unwrap_or(&segment);
+            oid_hex::parse_oid(hex).map(ID::ObjectId)
+        }
+        IdType::String if segment.is_empty() => return Err(PathError::Id(ParseIdError::Empty)),
+        IdType::String if segment.trim() != segment => {
+            return Err(PathError::Id(ParseIdError::Whitespace))
+        }
+        IdType::String => Some(ID::String(segment)),
+    };
+    id.map(|id| (collection, id))
+        .ok_or(PathError::WrongType(id_type))
+}
+
+fn segments(path: &str) -> Result<(String, String), PathError> {
+    let path = path.strip_prefix('/').ok_or(PathError::NotAnEntityPath)?;
+    let path = path.strip_suffix('/').unwrap_or(path);
+    let mut parts = path.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(collection), Some(id), None) if !collection.is_empty() && !id.is_empty() => {
+            Ok((decode(collection)?, decode(id)?))
+        }
+        _ => Err(PathError::NotAnEntityPath),
+    }
+}
+
+fn decode(segment: &str) -> Result<String, PathError> {
+    percent_decode_str(segment)
+        .decode_utf8()
+        .map(|s| s.into_owned())
+        .map_err(|_| PathError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_to_path() {
+        let oid = ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap();
+        assert_eq!(
+            to_path("users", &ID::ObjectId(oid)),
+            "/users/$oid:5eaefffa00c9fdf000c46fdc"
+        );
+        assert_eq!(to_path("users", &ID::Int64(-4)), "/users/-4");
+        assert_eq!(
+            to_path("team members", &ID::with_string("ü/?#%")),
+            "/team%20members/%C3%BC%2F%3F%23%25"
+        );
+        assert_eq!(to_path(".", &ID::with_string("..")), "/%2E/%2E%2E");
+        assert_eq!(to_path("users", &ID::with_string(".a.")), "/users/.a.");
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let oid = ID::ObjectId(ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap());
+        for id in [
+            oid.clone(),
+            ID::with_string("ü/?#%"),
+            ID::with_string("$oid"),
+            ID::with_string("."),
+            ID::with_string(".."),
+            ID::with_string("..."),
+        ] {
+            let path = to_path("team members", &id);
+            assert_eq!(parse_path(&path).unwrap(), ("team members".to_string(), id));
+        }
+        assert_eq!(parse_path("/users/1/").unwrap().1, ID::with_string("1"));
+        for path in &[
+            "",
+            "/",
+            "users/1",
+            "/users",
+            "/users/",
+            "//1",
+            "/users/1/x",
+            "/a//",
+        ] {
+            assert_eq!(
+                parse_path(path),
+                Err(PathError::NotAnEntityPath),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(parse_path("/users/%FF"), Err(PathError::InvalidEncoding));
+        assert_eq!(
+            parse_path("/users/$oid:123"),
+            Err(PathError::Id(ParseIdError::InvalidObjectId))
+        );
+        assert_eq!(
+            parse_path("/users/%20a"),
+            Err(PathError::Id(ParseIdError::Whitespace))
+        );
+    }
+
+    #[test]
+    fn test_parse_path_as() {
+        let oid = ID::ObjectId(ObjectId::with_string("5eaefffa00c9fdf000c46fdc").unwrap());
+        let cases = vec![
+            ("/users/42", IdType::Int64, Ok(ID::Int64(42))),
+            ("/users/-42", IdType::Int64, Ok(ID::Int64(-42))),
+            ("/users/4x", IdType::Int64, Err(IdType::Int64)),
+            ("/users/+42", IdType::Int64, Err(IdType::Int64)),
+            ("/users/42", IdType::String, Ok(ID::with_string("42"))),
+            (
+                "/users/$oid:5eaefffa00c9fdf000c46fdc",
+                IdType::String,
+                Ok(ID::with_string("$oid:5eaefffa00c9fdf000c46fdc")),
+            ),
+            (
+                "/users/$oid:123",
+                IdType::String,
+                Ok(ID::with_string("$oid:123")),
+            ),
+            (
+                "/users/$oid:5eaefffa00c9fdf000c46fdc",
+                IdType::ObjectId,
+                Ok(oid.clone()),
+            ),
+            ("/users/5eaefffa00c9fdf000c46fdc", IdType::ObjectId, Ok(oid)),
+            ("/users/42", IdType::ObjectId, Err(IdType::ObjectId)),
+        ];
+        for (path, id_type, expected) in cases {
+            let expected = expected
+                .map(|id| ("users".to_string(), id))
+                .map_err(PathError::WrongType);
+            assert_eq!(parse_path_as(path, id_type), expected, "{}", path);
+        }
+        assert_eq!(
+            parse_path_as("/users", IdType::Int64),
+            Err(PathError::NotAnEntityPath)
+        );
+        assert_eq!(
+            parse_path_as("/users/%20a", IdType::String),
+            Err(PathError::Id(ParseIdError::Whitespace))
+        );
+        let dots = ("..".to_string(), ID::with_string(".."));
+        assert_eq!(
+            parse_path_as(&to_path("..", &dots.1), IdType::String),
+            Ok(dots)
+        );
+        assert_eq!(
+            parse_path_as(&to_path("users", &ID::Int64(7)), IdType::Int64).unwrap(),
+            ("users".to_string(), ID::Int64(7))
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Policies restricting which IDs an application accepts
+
+use crate::{pipeline::IdType, ID};
+use std::{error, fmt, ops::RangeInclusive};
+
+/// Rules an ID must satisfy, checked by [`ID::validate`]
+///
+/// An empty policy accepts every ID. String rules only apply to String IDs and
+/// the integer range only to Int64 IDs.
+///
+/// ```
+/// use mongodb_id::{pipeline::IdType, IdPolicy, ID};
+///
+/// let policy = IdPolicy::new()
+///     .allow_types(&[IdType::String])
+///     .max_len(32)
+///     .prefix("inv_")
+///     .allowed_chars(|c| c.is_ascii_alphanumeric() || c == '_');
+/// assert!(ID::with_string("inv_42").validate(&policy).is_ok());
+/// assert!(ID::with_string("inv 42").validate(&policy).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct IdPolicy {
+    types: Option<Vec<IdType>>,
+    max_len: Option<usize>,
+    allowed_chars: Option<fn(char) -> bool>,
+    prefix: Option<String>,
+    int_range: Option<RangeInclusive<i64>>,
+    #[cfg(feature = "unicode")]
+    require_nfc: bool,
+    #[cfg(feature = "unicode")]
+    reject_confusables: bool,
+}
+
+impl IdPolicy {
+    pub fn new() -> Self {
+        IdPolicy::default()
+    }
+
+    /// Only accept IDs of these variants
+    pub fn allow_types(mut self, types: &[IdType]) -> Self {
+        self.types = Some(types.to_vec());
+        self
+    }
+
+    /// Maximum length of a String ID, in bytes
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Only accept String IDs whose every character passes `allowed`
+    pub fn allowed_chars(mut self, allowed: fn(char) -> bool) -> Self {
+        self.allowed_chars = Some(allowed);
+        self
+    }
+
+    /// Only accept String IDs starting with `prefix`
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Only accept Int64 IDs within `range`
+    pub fn int_range(mut self, range: RangeInclusive<i64>) -> Self {
+        self.int_range = Some(range);
+        self
+    }
+
+    /// Only accept String IDs in Unicode NFC
+    #[cfg(feature = "unicode")]
+    pub fn require_nfc(mut self) -> Self {
+        self.require_nfc = true;
+        self
+    }
+
+    /// Reject String IDs containing invisible characters or lookalikes of
+    /// Latin letters mixed with them
+    #[cfg(feature = "unicode")]
+    pub fn reject_confusables(mut self) -> Self {
+        self.reject_confusables = true;
+        self
+    }
+}
+
+/// The rule of an [`IdPolicy`] an ID broke
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidationError {
+    InvalidType { actual: IdType },
+    TooLong { max: usize, actual: usize },
+    InvalidChar { index: usize, ch: char },
+    MissingPrefix,
+    OutOfRange { value: i64 },
+    NotNormalized,
+    Confusable { index: usize, ch: char },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidType { actual } => {
+                write!(f, "{:?} IDs are not allowed", actual)
+            }
+            ValidationError::TooLong { max, actual } => {
+                write!(f, "ID is {} bytes long, the maximum is {}", actual, max)
+            }
+            ValidationError::InvalidChar { index, ch } => {
+                write!(f, "ID contains {:?} at byte {}", ch, index)
+            }
+            ValidationError::MissingPrefix => f.write_str("ID is missing the required prefix"),
+            ValidationError::OutOfRange { value } => {
+                write!(f, "ID {} is outside the allowed range", value)
+            }
+            ValidationError::NotNormalized => f.write_str("ID is not in Unicode NFC"),
+            ValidationError::Confusable { index, ch } => {
+                write!(f, "ID contains confusable {:?} at byte {}", ch, index)
+            }
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+impl ID {
+    /// Check the ID against `policy`, e.g. before using untrusted input in a query
+    pub fn validate(&self, policy: &IdPolicy) -> Result<(), ValidationError> {
+        let actual = IdType::from(self);
+        if let Some(types) = &policy.types {
+            if !types.contains(&actual) {
+                return Err(ValidationError::InvalidType { actual });
+            }
+        }
+        match self {
+            ID::ObjectId(_) => {}
+            ID::String(s) => {
+                if let Some(max) = policy.max_len {
+                    if s.len() > max {
+                        return Err(ValidationError::TooLong {
+                            max,
+                            actual: s.len(),
+                        });
+                    }
+                }
+                if let Some(prefix) = &policy.prefix {
+                    if !s.starts_with(prefix.as_str()) {
+                        return Err(ValidationError::MissingPrefix);
+                    }
+                }
+                if let Some(allowed) = policy.allowed_chars {
+                    if let Some((index, ch)) = s.char_indices().find(|(_, c)| !allowed(*c)) {
+                        return Err(ValidationError::InvalidChar { index, ch });
+                    }
+                }
+                #[cfg(feature = "unicode")]
+                {
+                    if policy.require_nfc && !crate::unicode::is_normalized(s) {
+                        return Err(ValidationError::NotNormalized);
+                    }
+                    if policy.reject_confusables {
+                        if let Some((index, ch)) = crate::unicode::find_confusable(s) {
+                            return Err(ValidationError::Confusable { index, ch });
+                        }
+                    }
+                }
+            }
+            ID::Int64(i) => {
+                if let Some(range) = &policy.int_range {
+                    if !range.contains(i) {
+                        return Err(ValidationError::OutOfRange { value: *i });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_validate() {
+        assert!(ID::with_string("").validate(&IdPolicy::new()).is_ok());
+
+        let policy = IdPolicy::new().allow_types(&[IdType::ObjectId, IdType::Int64]);
+        assert!(ID::ObjectId(ObjectId::new()).validate(&policy).is_ok());
+        assert_eq!(
+            ID::with_string("a").validate(&policy),
+            Err(ValidationError::InvalidType {
+                actual: IdType::String
+            })
+        );
+
+        let policy = IdPolicy::new()
+            .max_len(4)
+            .prefix("u")
+            .allowed_chars(|c| c.is_ascii_lowercase());
+        assert_eq!(
+            ID::with_string("users").validate(&policy),
+            Err(ValidationError::TooLong { max: 4, actual: 5 })
+        );
+        assert_eq!(
+            ID::with_string("abc").validate(&policy),
+            Err(ValidationError::MissingPrefix)
+        );
+        assert_eq!(
+            ID::with_string("uéa").validate(&policy),
+            Err(ValidationError::InvalidChar { index: 1, ch: 'é' })
+        );
+
+        let policy = IdPolicy::new().int_range(1..=i64::MAX);
+        assert!(ID::Int64(1).validate(&policy).is_ok());
+        assert_eq!(
+            ID::Int64(0).validate(&policy),
+            Err(ValidationError::OutOfRange { value: 0 })
+        );
+    }
+}